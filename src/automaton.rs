@@ -0,0 +1,506 @@
+use std::collections::HashMap;
+
+pub trait Cell: Copy + Eq {
+    fn is_fixed(self) -> bool;
+    fn is_alive(self) -> bool;
+    /// only called on cells that aren't fixed
+    fn with_alive(self, alive: bool) -> Self;
+}
+
+impl Cell for bool {
+    fn is_fixed(self) -> bool {
+        false
+    }
+
+    fn is_alive(self) -> bool {
+        self
+    }
+
+    fn with_alive(self, alive: bool) -> Self {
+        alive
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct Grid<C> {
+    width: usize,
+    height: usize,
+    cells: Vec<C>,
+}
+
+impl<C: Cell> Grid<C> {
+    pub fn new(rows: Vec<Vec<C>>) -> Grid<C> {
+        assert!(
+            rows.windows(2).all(|pair| pair[0].len() == pair[1].len()),
+            "every row of a grid should be the same length"
+        );
+        Grid {
+            width: rows.first().map_or(0, Vec::len),
+            height: rows.len(),
+            cells: rows.into_iter().flatten().collect(),
+        }
+    }
+
+    pub fn height(&self) -> usize {
+        self.height
+    }
+
+    pub fn width(&self) -> usize {
+        self.width
+    }
+
+    pub fn rows(&self) -> impl Iterator<Item = &[C]> {
+        // max(1) keeps an empty grid from asking for chunks of nothing
+        self.cells.chunks(self.width.max(1))
+    }
+
+    pub fn get(&self, row: isize, col: isize) -> Option<C> {
+        if row < 0 || col < 0 || row as usize >= self.height || col as usize >= self.width {
+            return None;
+        }
+        Some(self.cells[self.index(row as usize, col as usize)])
+    }
+
+    pub fn alive_count(&self) -> usize {
+        self.cells.iter().filter(|c| c.is_alive()).count()
+    }
+
+    fn index(&self, row: usize, col: usize) -> usize {
+        row * self.width + col
+    }
+
+    fn position(&self, index: usize) -> (usize, usize) {
+        (index / self.width, index % self.width)
+    }
+}
+
+pub const DIRECTIONS: [(isize, isize); 8] = [
+    (-1, -1),
+    (-1, 0),
+    (-1, 1),
+    (0, -1),
+    (0, 1),
+    (1, -1),
+    (1, 0),
+    (1, 1),
+];
+
+/// An [`Automaton`] asks once for every cell when it's created, so the answer may depend on
+/// where the fixed cells are but not on which cells are alive.
+pub trait Neighborhood<C> {
+    fn neighbors(&self, grid: &Grid<C>, row: usize, col: usize) -> Vec<(usize, usize)>;
+}
+
+impl<C, F> Neighborhood<C> for F
+where
+    F: Fn(&Grid<C>, usize, usize) -> Vec<(usize, usize)>,
+{
+    fn neighbors(&self, grid: &Grid<C>, row: usize, col: usize) -> Vec<(usize, usize)> {
+        self(grid, row, col)
+    }
+}
+
+pub struct Offsets(pub Vec<(isize, isize)>);
+
+impl<C: Cell> Neighborhood<C> for Offsets {
+    fn neighbors(&self, grid: &Grid<C>, row: usize, col: usize) -> Vec<(usize, usize)> {
+        self.0
+            .iter()
+            .map(|&(dr, dc)| (row as isize + dr, col as isize + dc))
+            .filter(|&(r, c)| grid.get(r, c).is_some())
+            .map(|(r, c)| (r as usize, c as usize))
+            .collect()
+    }
+}
+
+pub struct Adjacent;
+
+impl<C: Cell> Neighborhood<C> for Adjacent {
+    fn neighbors(&self, grid: &Grid<C>, row: usize, col: usize) -> Vec<(usize, usize)> {
+        Offsets(DIRECTIONS.to_vec()).neighbors(grid, row, col)
+    }
+}
+
+/// the first cell in each direction that isn't fixed, looking past fixed ones
+pub struct LineOfSight;
+
+impl<C: Cell> Neighborhood<C> for LineOfSight {
+    fn neighbors(&self, grid: &Grid<C>, row: usize, col: usize) -> Vec<(usize, usize)> {
+        DIRECTIONS
+            .iter()
+            .filter_map(|&(dr, dc)| {
+                let (mut r, mut c) = (row as isize + dr, col as isize + dc);
+                while grid.get(r, c)?.is_fixed() {
+                    r += dr;
+                    c += dc;
+                }
+                Some((r as usize, c as usize))
+            })
+            .collect()
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Rule {
+    birth: Vec<usize>,
+    survival: Vec<usize>,
+}
+
+impl Rule {
+    pub fn new(birth: &[usize], survival: &[usize]) -> Rule {
+        Rule {
+            birth: birth.to_vec(),
+            survival: survival.to_vec(),
+        }
+    }
+
+    pub fn conway() -> Rule {
+        Rule::new(&[3], &[2, 3])
+    }
+
+    fn next(&self, alive: bool, live_neighbors: usize) -> bool {
+        if alive {
+            self.survival.contains(&live_neighbors)
+        } else {
+            self.birth.contains(&live_neighbors)
+        }
+    }
+}
+
+/// Every cell's neighbors as flat indices, packed into one buffer with `starts[i]` marking
+/// where cell `i`'s run begins.
+struct NeighborTable {
+    starts: Vec<usize>,
+    neighbors: Vec<usize>,
+}
+
+impl NeighborTable {
+    fn new<I: IntoIterator<Item = Vec<usize>>>(lists: I) -> NeighborTable {
+        let mut starts = vec![0];
+        let mut neighbors = Vec::new();
+        for list in lists {
+            neighbors.extend(list);
+            starts.push(neighbors.len());
+        }
+        NeighborTable { starts, neighbors }
+    }
+
+    fn of(&self, i: usize) -> &[usize] {
+        &self.neighbors[self.starts[i]..self.starts[i + 1]]
+    }
+
+    fn reversed(&self) -> NeighborTable {
+        let mut lists = vec![Vec::new(); self.starts.len() - 1];
+        for i in 0..lists.len() {
+            for &j in self.of(i) {
+                lists[j].push(i);
+            }
+        }
+        NeighborTable::new(lists)
+    }
+}
+
+/// Only cells watching something that changed last generation get looked at again.
+pub struct Automaton<C> {
+    grid: Grid<C>,
+    back: Vec<C>,
+    /// indices of the cells that might change next generation
+    frontier: Vec<usize>,
+    queued: Vec<bool>,
+    neighbors: NeighborTable,
+    watchers: NeighborTable,
+    rule: Rule,
+    generation: usize,
+    /// the xor of [`zobrist_key`] for every live cell, kept up to date as cells change
+    hash: u64,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Settled {
+    Stable { generation: usize },
+    Cycle { start: usize, period: usize },
+    GenerationLimit,
+}
+
+/// A pseudorandom key for the cell at `index`, from splitmix64, so a grid's hash can be the xor
+/// of its live cells' keys and be updated one change at a time.
+fn zobrist_key(index: usize) -> u64 {
+    let mut z = (index as u64)
+        .wrapping_add(1)
+        .wrapping_mul(0x9e37_79b9_7f4a_7c15);
+    z = (z ^ (z >> 30)).wrapping_mul(0xbf58_476d_1ce4_e5b9);
+    z = (z ^ (z >> 27)).wrapping_mul(0x94d0_49bb_1331_11eb);
+    z ^ (z >> 31)
+}
+
+impl<C: Cell> Automaton<C> {
+    /// fixed cells never change so they get no neighbors
+    pub fn new<N: Neighborhood<C>>(grid: Grid<C>, neighborhood: N, rule: Rule) -> Automaton<C> {
+        let neighbors = NeighborTable::new((0..grid.cells.len()).map(|i| {
+            if grid.cells[i].is_fixed() {
+                return Vec::new();
+            }
+            let (row, col) = grid.position(i);
+            neighborhood
+                .neighbors(&grid, row, col)
+                .into_iter()
+                .map(|(r, c)| grid.index(r, c))
+                .collect()
+        }));
+        let frontier = (0..grid.cells.len())
+            .filter(|&i| !grid.cells[i].is_fixed())
+            .collect();
+        let hash = (0..grid.cells.len())
+            .filter(|&i| grid.cells[i].is_alive())
+            .fold(0, |hash, i| hash ^ zobrist_key(i));
+        Automaton {
+            back: grid.cells.clone(),
+            queued: vec![false; grid.cells.len()],
+            watchers: neighbors.reversed(),
+            neighbors,
+            grid,
+            frontier,
+            rule,
+            generation: 0,
+            hash,
+        }
+    }
+
+    pub fn grid(&self) -> &Grid<C> {
+        &self.grid
+    }
+
+    pub fn generation(&self) -> usize {
+        self.generation
+    }
+
+    pub fn live_neighbors(&self, row: usize, col: usize) -> usize {
+        self.count_live(self.grid.index(row, col))
+    }
+
+    fn count_live(&self, i: usize) -> usize {
+        self.neighbors
+            .of(i)
+            .iter()
+            .filter(|&&j| self.grid.cells[j].is_alive())
+            .count()
+    }
+
+    pub fn step(&mut self) -> bool {
+        // the back buffer is a generation behind, but only in cells that changed last time, and
+        // those are all on the frontier so get overwritten here
+        let frontier = std::mem::take(&mut self.frontier);
+        let mut next_frontier = Vec::new();
+        for &i in &frontier {
+            let cell = self.grid.cells[i];
+            let alive = self.rule.next(cell.is_alive(), self.count_live(i));
+            self.back[i] = cell.with_alive(alive);
+            if alive != cell.is_alive() {
+                self.hash ^= zobrist_key(i);
+                for &j in std::iter::once(&i).chain(self.watchers.of(i)) {
+                    if !self.queued[j] && !self.grid.cells[j].is_fixed() {
+                        self.queued[j] = true;
+                        next_frontier.push(j);
+                    }
+                }
+            }
+        }
+        for &i in &next_frontier {
+            self.queued[i] = false;
+        }
+        std::mem::swap(&mut self.grid.cells, &mut self.back);
+        self.frontier = next_frontier;
+        self.generation += 1;
+        !self.frontier.is_empty()
+    }
+
+    /// never returns if the grid keeps changing, like an oscillator would
+    pub fn run_until_stable(&mut self) -> usize {
+        while self.step() {}
+        self.generation - 1
+    }
+
+    pub fn settle(&mut self, max_generations: usize) -> Settled {
+        self.settle_with(max_generations, |_| {})
+    }
+
+    /// Every state is kept so a hash collision can't be mistaken for a repeat.
+    pub fn settle_with<F: FnMut(&Grid<C>)>(
+        &mut self,
+        max_generations: usize,
+        mut on_generation: F,
+    ) -> Settled {
+        let limit = self.generation + max_generations;
+        let mut seen = HashMap::new();
+        self.remember(&mut seen);
+        while self.generation < limit {
+            if !self.step() {
+                return Settled::Stable {
+                    generation: self.generation - 1,
+                };
+            }
+            if let Some(start) = self.remember(&mut seen) {
+                return Settled::Cycle {
+                    start,
+                    period: self.generation - start,
+                };
+            }
+            on_generation(&self.grid);
+        }
+        Settled::GenerationLimit
+    }
+
+    fn remember(&self, seen: &mut HashMap<u64, Vec<(usize, Vec<C>)>>) -> Option<usize> {
+        let same_hash = seen.entry(self.hash).or_default();
+        if let Some(&(start, _)) = same_hash
+            .iter()
+            .find(|(_, cells)| *cells == self.grid.cells)
+        {
+            return Some(start);
+        }
+        same_hash.push((self.generation, self.grid.cells.clone()));
+        None
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    fn life(rows: &[&str]) -> Grid<bool> {
+        Grid::new(
+            rows.iter()
+                .map(|row| row.chars().map(|c| c == '#').collect())
+                .collect(),
+        )
+    }
+
+    #[test]
+    fn test_blinker() {
+        let vertical = life(&[".....", "..#..", "..#..", "..#..", "....."]);
+        let horizontal = life(&[".....", ".....", ".###.", ".....", "....."]);
+        let mut automaton = Automaton::new(vertical.clone(), Adjacent, Rule::conway());
+        assert!(automaton.step());
+        assert_eq!(automaton.grid(), &horizontal);
+        assert!(automaton.step());
+        assert_eq!(automaton.grid(), &vertical);
+        assert_eq!(automaton.generation(), 2);
+    }
+
+    #[test]
+    fn test_glider_crosses_quiet_grid() {
+        let mut rows = vec![vec![false; 12]; 12];
+        for &(r, c) in &[(0, 1), (1, 2), (2, 0), (2, 1), (2, 2)] {
+            rows[r][c] = true;
+        }
+        let mut automaton = Automaton::new(Grid::new(rows), Adjacent, Rule::conway());
+        // a glider moves one cell diagonally every four generations
+        for _ in 0..4 {
+            automaton.step();
+        }
+        let grid = automaton.grid();
+        let alive = (0..12)
+            .flat_map(|r| (0..12).map(move |c| (r, c)))
+            .filter(|&(r, c)| grid.get(r, c).unwrap())
+            .collect::<Vec<_>>();
+        assert_eq!(alive, vec![(1, 2), (2, 3), (3, 1), (3, 2), (3, 3)]);
+        // only the cells around the glider are left to check
+        assert!(automaton.frontier.len() < 30);
+    }
+
+    #[test]
+    fn test_settle() {
+        let blinker = life(&[".....", "..#..", "..#..", "..#..", "....."]);
+        let mut automaton = Automaton::new(blinker.clone(), Adjacent, Rule::conway());
+        assert_eq!(
+            automaton.settle(100),
+            Settled::Cycle {
+                start: 0,
+                period: 2
+            }
+        );
+
+        // a glider on a bounded grid crashes into the corner and ends up a block
+        let mut rows = vec![vec![false; 8]; 8];
+        for &(r, c) in &[(0, 1), (1, 2), (2, 0), (2, 1), (2, 2)] {
+            rows[r][c] = true;
+        }
+        let mut automaton = Automaton::new(Grid::new(rows), Adjacent, Rule::conway());
+        let mut seen = 0;
+        let settled = automaton.settle_with(100, |_| seen += 1);
+        assert_eq!(settled, Settled::Stable { generation: 23 });
+        assert_eq!(seen, 23);
+        assert_eq!(automaton.grid().alive_count(), 4);
+
+        let mut automaton = Automaton::new(blinker, Adjacent, Rule::conway());
+        assert_eq!(automaton.settle(1), Settled::GenerationLimit);
+        // the limit counts from where it's called, and the first state is remembered afresh
+        assert_eq!(
+            automaton.settle(2),
+            Settled::Cycle {
+                start: 1,
+                period: 2
+            }
+        );
+    }
+
+    #[test]
+    fn test_hash_collision_is_not_a_cycle() {
+        let mut automaton = Automaton::new(life(&["#.", ".."]), Adjacent, Rule::conway());
+        let mut seen = HashMap::new();
+        assert_eq!(automaton.remember(&mut seen), None);
+        // a different grid that happens to have the same hash
+        automaton.grid.cells = vec![false, true, false, false];
+        automaton.generation = 1;
+        assert_eq!(automaton.remember(&mut seen), None);
+        assert_eq!(seen[&automaton.hash].len(), 2);
+        automaton.grid.cells = vec![true, false, false, false];
+        automaton.generation = 2;
+        assert_eq!(automaton.remember(&mut seen), Some(0));
+    }
+
+    #[test]
+    fn test_block_is_stable() {
+        let block = life(&["....", ".##.", ".##.", "...."]);
+        let mut automaton = Automaton::new(block.clone(), Adjacent, Rule::conway());
+        assert_eq!(automaton.run_until_stable(), 0);
+        assert_eq!(automaton.grid(), &block);
+        assert_eq!(automaton.grid().alive_count(), 4);
+    }
+
+    #[test]
+    fn test_custom_neighborhoods() {
+        let grid = life(&["#..", "...", "..#"]);
+        assert_eq!(
+            Offsets(vec![(0, 1), (1, 0), (-1, 0), (0, -1)]).neighbors(&grid, 0, 0),
+            vec![(0, 1), (1, 0)]
+        );
+        // a torus, wrapping around every edge
+        let torus = |grid: &Grid<bool>, row: usize, col: usize| {
+            let (height, width) = (grid.height() as isize, grid.width() as isize);
+            DIRECTIONS
+                .iter()
+                .map(|&(dr, dc)| {
+                    (
+                        (row as isize + dr).rem_euclid(height) as usize,
+                        (col as isize + dc).rem_euclid(width) as usize,
+                    )
+                })
+                .collect()
+        };
+        assert!(torus.neighbors(&grid, 0, 0).contains(&(2, 2)));
+        assert!(!Adjacent.neighbors(&grid, 0, 0).contains(&(2, 2)));
+    }
+
+    #[test]
+    fn test_lopsided_neighborhood() {
+        // every cell only looks at the one to its left, so a live cell spreads rightwards and
+        // the frontier has to follow it even though nothing looks back
+        let grid = life(&["#...."]);
+        let mut automaton = Automaton::new(grid, Offsets(vec![(0, -1)]), Rule::new(&[1], &[0, 1]));
+        assert_eq!(automaton.live_neighbors(0, 1), 1);
+        assert_eq!(automaton.live_neighbors(0, 0), 0);
+        assert_eq!(automaton.run_until_stable(), 4);
+        assert_eq!(automaton.grid(), &life(&["#####"]));
+    }
+}
@@ -1,5 +1,10 @@
+use std::collections::BTreeSet;
+use std::ops::{Range, RangeInclusive};
 use std::str::FromStr;
 
+const ROWS: i32 = 128;
+const COLS: i32 = 8;
+
 pub struct Seat {
     row: i32,
     col: i32,
@@ -45,12 +50,12 @@ impl Seat {
     }
 
     pub fn get_seat_id(&self) -> i32 {
-        self.row * 8 + self.col
+        self.row * COLS + self.col
     }
 
     fn get_seat_location(seat: &[char]) -> (i32, i32) {
-        let mut row = HighLowBounds::new(0, 128);
-        let mut col = HighLowBounds::new(0, 8);
+        let mut row = HighLowBounds::new(0, ROWS);
+        let mut col = HighLowBounds::new(0, COLS);
 
         for char in seat {
             match char {
@@ -76,9 +81,54 @@ impl FromStr for Seat {
     }
 }
 
+pub struct SeatingChart {
+    taken: BTreeSet<i32>,
+}
+
+impl SeatingChart {
+    pub fn new(seats: &[Seat]) -> SeatingChart {
+        SeatingChart {
+            taken: seats.iter().map(Seat::get_seat_id).collect(),
+        }
+    }
+
+    pub fn empty_seats_between_occupied(&self) -> Vec<i32> {
+        self.gaps()
+            .into_iter()
+            .filter(|gap| gap.start() == gap.end())
+            .map(|gap| *gap.start())
+            .collect()
+    }
+
+    /// only between the first and last occupied seat
+    pub fn gaps(&self) -> Vec<RangeInclusive<i32>> {
+        self.taken
+            .iter()
+            .zip(self.taken.iter().skip(1))
+            .filter(|(&lo, &hi)| hi - lo > 1)
+            .map(|(&lo, &hi)| (lo + 1)..=(hi - 1))
+            .collect()
+    }
+
+    /// rows with no occupied seats don't exist on this plane
+    pub fn missing_front_rows(&self) -> Range<i32> {
+        match self.taken.iter().next() {
+            Some(first) => 0..(first / COLS),
+            None => 0..ROWS,
+        }
+    }
+
+    pub fn missing_back_rows(&self) -> Range<i32> {
+        match self.taken.iter().next_back() {
+            Some(last) => (last / COLS + 1)..ROWS,
+            None => 0..ROWS,
+        }
+    }
+}
+
 #[cfg(test)]
 mod test {
-    use crate::day05::Seat;
+    use crate::day05::{Seat, SeatingChart};
     use crate::loader::file_to_vec;
     use std::str::FromStr;
 
@@ -119,4 +169,39 @@ mod test {
             + 1;
         assert_eq!(result, 617);
     }
+
+    #[test]
+    fn test_seating_chart_large() {
+        let input = file_to_vec::<Seat>("inputs/day05.txt");
+        let chart = SeatingChart::new(&input);
+        assert_eq!(chart.empty_seats_between_occupied(), vec![617]);
+        assert_eq!(chart.gaps(), vec![617..=617]);
+    }
+
+    #[test]
+    fn test_seating_chart_gaps() {
+        let input = [
+            "FFFFFFBLLL",
+            "FFFFFFBLLR",
+            "FFFFFFBLRR",
+            "FFFFFFBRRR",
+            "FFFFFBFLLL",
+        ]
+        .iter()
+        .map(|s| s.parse::<Seat>().unwrap())
+        .collect::<Vec<_>>();
+        let chart = SeatingChart::new(&input);
+        assert_eq!(chart.empty_seats_between_occupied(), vec![10]);
+        assert_eq!(chart.gaps(), vec![10..=10, 12..=14]);
+        assert_eq!(chart.missing_front_rows(), 0..1);
+        assert_eq!(chart.missing_back_rows(), 3..128);
+    }
+
+    #[test]
+    fn test_seating_chart_empty() {
+        let chart = SeatingChart::new(&[]);
+        assert!(chart.gaps().is_empty());
+        assert_eq!(chart.missing_front_rows(), 0..128);
+        assert_eq!(chart.missing_back_rows(), 0..128);
+    }
 }
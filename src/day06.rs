@@ -1,15 +1,154 @@
 use std::collections::HashMap;
+use std::fmt;
+use std::iter::FromIterator;
+use std::ops::{BitAnd, BitOr};
 use std::str::FromStr;
 
+/// One bit per question `a..=z`.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct AnswerSet(u32);
+
+impl AnswerSet {
+    pub const ALL: AnswerSet = AnswerSet((1 << 26) - 1);
+
+    pub fn new() -> AnswerSet {
+        AnswerSet(0)
+    }
+
+    fn bit(question: char) -> u32 {
+        assert!(
+            question.is_ascii_lowercase(),
+            "questions should be in a..=z, instead got {}",
+            question
+        );
+        1 << (question as u32 - 'a' as u32)
+    }
+
+    pub fn insert(&mut self, question: char) {
+        self.0 |= AnswerSet::bit(question)
+    }
+
+    pub fn contains(&self, question: char) -> bool {
+        question.is_ascii_lowercase() && self.0 & AnswerSet::bit(question) != 0
+    }
+
+    pub fn len(&self) -> usize {
+        self.0.count_ones() as usize
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.0 == 0
+    }
+
+    pub fn union(self, other: AnswerSet) -> AnswerSet {
+        AnswerSet(self.0 | other.0)
+    }
+
+    pub fn intersection(self, other: AnswerSet) -> AnswerSet {
+        AnswerSet(self.0 & other.0)
+    }
+
+    pub fn iter(&self) -> impl Iterator<Item = char> + '_ {
+        ('a'..='z').filter(move |&c| self.contains(c))
+    }
+}
+
+impl BitOr for AnswerSet {
+    type Output = AnswerSet;
+
+    fn bitor(self, rhs: AnswerSet) -> Self::Output {
+        self.union(rhs)
+    }
+}
+
+impl BitAnd for AnswerSet {
+    type Output = AnswerSet;
+
+    fn bitand(self, rhs: AnswerSet) -> Self::Output {
+        self.intersection(rhs)
+    }
+}
+
+impl FromIterator<char> for AnswerSet {
+    fn from_iter<T: IntoIterator<Item = char>>(iter: T) -> Self {
+        let mut set = AnswerSet::new();
+        for c in iter {
+            set.insert(c)
+        }
+        set
+    }
+}
+
+impl FromStr for AnswerSet {
+    type Err = ();
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        if s.chars().all(|c| c.is_ascii_lowercase()) {
+            Ok(s.chars().collect())
+        } else {
+            Err(())
+        }
+    }
+}
+
+impl fmt::Display for AnswerSet {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.iter().collect::<String>())
+    }
+}
+
 #[derive(Debug)]
 pub struct Group {
-    yeses: HashMap<char, usize>,
-    size: usize,
+    people: Vec<AnswerSet>,
 }
 
 impl Group {
+    pub fn size(&self) -> usize {
+        self.people.len()
+    }
+
+    pub fn people(&self) -> &[AnswerSet] {
+        &self.people
+    }
+
+    pub fn union(&self) -> AnswerSet {
+        self.people.iter().fold(AnswerSet::new(), |acc, &x| acc | x)
+    }
+
+    pub fn intersection(&self) -> AnswerSet {
+        if self.people.is_empty() {
+            return AnswerSet::new();
+        }
+        self.people.iter().fold(AnswerSet::ALL, |acc, &x| acc & x)
+    }
+
+    pub fn answered_by_exactly(&self, k: usize) -> AnswerSet {
+        if k == 0 {
+            // the histogram leaves these out
+            return AnswerSet(AnswerSet::ALL.0 & !self.union().0);
+        }
+        self.histogram()
+            .into_iter()
+            .filter(|(_, count)| *count == k)
+            .map(|(c, _)| c)
+            .collect()
+    }
+
+    /// questions nobody answered are left out
+    pub fn histogram(&self) -> HashMap<char, usize> {
+        let mut histogram = HashMap::new();
+        for c in self.people.iter().flat_map(AnswerSet::iter) {
+            *histogram.entry(c).or_insert(0) += 1;
+        }
+        histogram
+    }
+
+    pub fn amount_any_yeses(&self) -> usize {
+        self.union().len()
+    }
+
     pub fn amount_all_yeses(&self) -> usize {
-        self.yeses.iter().filter(|(_, i)| self.size.eq(i)).count()
+        self.intersection().len()
     }
 }
 
@@ -17,24 +156,20 @@ impl FromStr for Group {
     type Err = ();
 
     fn from_str(s: &str) -> Result<Self, Self::Err> {
-        let size = s.chars().filter(|c| c == &' ').count() + 1;
-        let filtered = s.chars().filter(|c| c != &' ').collect::<Vec<char>>();
-        let mut yeses = HashMap::new();
-        for char in filtered {
-            if let Some(&amount) = yeses.get(&char) {
-                yeses.insert(char, amount + 1);
-            } else {
-                yeses.insert(char, 1);
-            }
-        }
-        Ok(Group { yeses, size })
+        let people = s
+            .split(' ')
+            .filter(|person| !person.is_empty())
+            .map(AnswerSet::from_str)
+            .collect::<Result<_, _>>()?;
+        Ok(Group { people })
     }
 }
 
 #[cfg(test)]
 mod test {
-    use crate::day06::Group;
+    use crate::day06::{AnswerSet, Group};
     use crate::loader::file_to_vec_by_blank_lines;
+    use std::collections::HashMap;
 
     #[test]
     fn test_small() {
@@ -57,4 +192,80 @@ mod test {
         );
         assert_eq!(result, 3290)
     }
+
+    #[test]
+    fn test_small_p1() {
+        let result = file_to_vec_by_blank_lines::<Group>("inputs/day06small.txt")
+            .iter()
+            .fold(0, |acc, x| acc + x.amount_any_yeses());
+        assert_eq!(result, 11)
+    }
+
+    #[test]
+    fn test_set_queries() {
+        let group = "abc ab ac".parse::<Group>().unwrap();
+        assert_eq!(group.size(), 3);
+        assert_eq!(group.union(), "abc".parse().unwrap());
+        assert_eq!(group.intersection(), "a".parse().unwrap());
+        assert_eq!(group.answered_by_exactly(2), "bc".parse().unwrap());
+        assert!(group.answered_by_exactly(1).is_empty());
+        assert_eq!(group.answered_by_exactly(0).len(), 23);
+        assert!(!group.answered_by_exactly(0).contains('c'));
+        assert!(group.answered_by_exactly(0).contains('d'));
+        assert_eq!(group.histogram()[&'a'], 3);
+        assert_eq!(group.histogram()[&'b'], 2);
+        assert_eq!(group.histogram().get(&'d'), None);
+
+        let empty = "".parse::<Group>().unwrap();
+        assert!(empty.intersection().is_empty());
+        assert_eq!(empty.amount_all_yeses(), 0);
+        assert_eq!(empty.answered_by_exactly(0), AnswerSet::ALL);
+    }
+
+    #[test]
+    fn test_answer_set() {
+        let set = "zax".parse::<AnswerSet>().unwrap();
+        assert_eq!(set.len(), 3);
+        assert!(set.contains('z') && set.contains('a') && !set.contains('b'));
+        assert_eq!(set.to_string(), "axz");
+        assert_eq!(AnswerSet::ALL.len(), 26);
+        assert!("aB".parse::<AnswerSet>().is_err());
+    }
+
+    #[test]
+    fn test_bench_against_hashmap() {
+        let people = [
+            "abcxyz",
+            "qwerty",
+            "abc",
+            "zyxwvutsrqponmlkjihgfedcba",
+            "mno",
+        ];
+        let input = (0..100_000)
+            .map(|i| people[i % people.len()..].join(" "))
+            .collect::<Vec<_>>();
+
+        let hashmap_result = timed!(
+            input
+                .iter()
+                .map(|s| {
+                    let size = s.split(' ').count();
+                    let mut yeses = HashMap::new();
+                    for c in s.chars().filter(|&c| c != ' ') {
+                        *yeses.entry(c).or_insert(0) += 1;
+                    }
+                    yeses.values().filter(|&&i| i == size).count()
+                })
+                .sum::<usize>(),
+            "hashmap"
+        );
+        let bitset_result = timed!(
+            input
+                .iter()
+                .map(|s| s.parse::<Group>().unwrap().amount_all_yeses())
+                .sum::<usize>(),
+            "bitset"
+        );
+        assert_eq!(hashmap_result, bitset_result);
+    }
 }
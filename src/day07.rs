@@ -1,8 +1,10 @@
-use std::collections::HashSet;
+use std::collections::{HashMap, HashSet, VecDeque};
+use std::fmt;
 use std::hash::Hash;
+use std::iter::FromIterator;
 use std::str::FromStr;
 
-#[derive(Hash, Eq, PartialEq)]
+#[derive(Debug, Clone, Hash, Eq, PartialEq)]
 pub struct BagType {
     adj: String,
     color: String,
@@ -11,21 +13,25 @@ pub struct BagType {
 impl FromStr for BagType {
     type Err = ();
 
+    /// every word before the color is part of the adjective
     fn from_str(s: &str) -> Result<Self, Self::Err> {
-        let (adj, color) = s.split_once(" ").unwrap_or_else(|| {
-            panic!(
-                "attempted to split_once with a \" \" and failed on \"{}\"",
-                s
-            )
-        });
-        let adj = String::from(adj);
-        let color = String::from(color.split(' ').next().unwrap_or_else(|| {
-            panic!(
-                "expected at least one value when splitting \"{}\" by \" \" but found none",
-                color
-            )
-        }));
-        Ok(BagType { adj, color })
+        let mut words = s.split_whitespace().collect::<Vec<_>>();
+        if let Some(&"bag") | Some(&"bag.") | Some(&"bags") | Some(&"bags.") = words.last() {
+            words.pop();
+        }
+        match words.split_last() {
+            Some((color, adj)) if !adj.is_empty() => Ok(BagType {
+                adj: adj.join(" "),
+                color: String::from(*color),
+            }),
+            _ => Err(()),
+        }
+    }
+}
+
+impl fmt::Display for BagType {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{} {}", self.adj, self.color)
     }
 }
 
@@ -71,52 +77,452 @@ impl FromStr for Bag {
     }
 }
 
-impl<'a> Default for HashMatrix<'a> {
-    fn default() -> Self {
-        Self::new()
+impl fmt::Display for Bag {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{} bags contain ", self.bag_type)?;
+        if self.inner.is_empty() {
+            return write!(f, "no other bags.");
+        }
+        let contents = self
+            .inner
+            .iter()
+            .map(|(bag_type, num)| match num {
+                1 => format!("1 {} bag", bag_type),
+                num => format!("{} {} bags", num, bag_type),
+            })
+            .collect::<Vec<_>>();
+        write!(f, "{}.", contents.join(", "))
+    }
+}
+
+#[derive(Debug, PartialEq)]
+pub enum BagRuleError {
+    SelfContaining(BagType),
+    /// bags that end up inside themselves, in the order they contain one another
+    Cycle(Vec<BagType>),
+    Undeclared {
+        bag_type: BagType,
+        referenced_by: BagType,
+    },
+}
+
+impl fmt::Display for BagRuleError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            BagRuleError::SelfContaining(bag_type) => {
+                write!(f, "{} bags contain themselves", bag_type)
+            }
+            BagRuleError::Cycle(cycle) => write!(
+                f,
+                "cycle: {} -> {}",
+                cycle
+                    .iter()
+                    .map(ToString::to_string)
+                    .collect::<Vec<_>>()
+                    .join(" -> "),
+                cycle[0]
+            ),
+            BagRuleError::Undeclared {
+                bag_type,
+                referenced_by,
+            } => write!(
+                f,
+                "{} bags are inside {} bags but have no rule",
+                bag_type, referenced_by
+            ),
+        }
     }
 }
 
-pub struct HashMatrix<'a> {
-    matrix: Vec<(&'a BagType, &'a BagType, i32)>,
+#[derive(Clone, Copy, PartialEq)]
+enum Mark<T = ()> {
+    Unvisited,
+    InProgress,
+    Done(T),
 }
 
-impl<'a> HashMatrix<'a> {
-    pub fn new() -> HashMatrix<'a> {
-        HashMatrix { matrix: Vec::new() }
+pub enum GraphView<'a> {
+    Whole,
+    Inside(&'a BagType),
+    Outside(&'a BagType),
+}
+
+pub type BagId = usize;
+
+type Edges = fn(&BagGraph, BagId) -> &[(BagId, i32)];
+
+#[derive(Debug, Default)]
+pub struct BagGraph {
+    ids: HashMap<BagType, BagId>,
+    bag_types: Vec<BagType>,
+    declared: Vec<bool>,
+    contains: Vec<Vec<(BagId, i32)>>,
+    contained_by: Vec<Vec<(BagId, i32)>>,
+}
+
+impl BagGraph {
+    pub fn new() -> BagGraph {
+        BagGraph::default()
+    }
+
+    pub fn build<'a>(
+        bags: impl IntoIterator<Item = &'a Bag>,
+    ) -> Result<BagGraph, Vec<BagRuleError>> {
+        let graph = bags.into_iter().collect::<BagGraph>();
+        graph.validate()?;
+        Ok(graph)
+    }
+
+    pub fn validate(&self) -> Result<(), Vec<BagRuleError>> {
+        let mut errors = Vec::new();
+        for id in 0..self.len() {
+            if !self.declared[id] {
+                for &(outer, _) in self.outer(id) {
+                    errors.push(BagRuleError::Undeclared {
+                        bag_type: self.bag_type(id).clone(),
+                        referenced_by: self.bag_type(outer).clone(),
+                    })
+                }
+            }
+            if self.inner(id).iter().any(|&(inner, _)| inner == id) {
+                errors.push(BagRuleError::SelfContaining(self.bag_type(id).clone()))
+            }
+        }
+        for cycle in self.find_cycles() {
+            errors.push(BagRuleError::Cycle(
+                cycle
+                    .into_iter()
+                    .map(|id| self.bag_type(id).clone())
+                    .collect(),
+            ))
+        }
+        if errors.is_empty() {
+            Ok(())
+        } else {
+            Err(errors)
+        }
+    }
+
+    /// cycles closed by back edges of a depth first search, self containing bags excluded
+    fn find_cycles(&self) -> Vec<Vec<BagId>> {
+        let mut marks = vec![Mark::Unvisited; self.len()];
+        let mut cycles = Vec::new();
+        for root in 0..self.len() {
+            if marks[root] != Mark::Unvisited {
+                continue;
+            }
+            marks[root] = Mark::InProgress;
+            let mut path = vec![root];
+            let mut next_edge = vec![0];
+            while let Some(&id) = path.last() {
+                let edge = next_edge.last_mut().unwrap();
+                if let Some(&(inner, _)) = self.inner(id).get(*edge) {
+                    *edge += 1;
+                    match marks[inner] {
+                        Mark::Unvisited => {
+                            marks[inner] = Mark::InProgress;
+                            path.push(inner);
+                            next_edge.push(0);
+                        }
+                        Mark::InProgress if inner != id => {
+                            let start = path.iter().position(|&p| p == inner).unwrap();
+                            cycles.push(path[start..].to_vec());
+                        }
+                        _ => {}
+                    }
+                } else {
+                    marks[id] = Mark::Done(());
+                    path.pop();
+                    next_edge.pop();
+                }
+            }
+        }
+        cycles
+    }
+
+    fn intern(&mut self, bag_type: &BagType) -> BagId {
+        if let Some(&id) = self.ids.get(bag_type) {
+            return id;
+        }
+        let id = self.bag_types.len();
+        self.ids.insert(bag_type.clone(), id);
+        self.bag_types.push(bag_type.clone());
+        self.declared.push(false);
+        self.contains.push(Vec::new());
+        self.contained_by.push(Vec::new());
+        id
     }
 
-    pub fn add(&mut self, bag: &'a Bag) {
-        let Bag { bag_type, inner } = bag;
-        for (inner_bag, num) in inner {
-            self.matrix.push((&bag_type, &inner_bag, *num));
+    pub fn add(&mut self, bag: &Bag) {
+        let outer = self.intern(&bag.bag_type);
+        self.declared[outer] = true;
+        for (inner_bag, num) in &bag.inner {
+            let inner = self.intern(inner_bag);
+            self.contains[outer].push((inner, *num));
+            self.contained_by[inner].push((outer, *num));
         }
     }
 
+    pub fn id(&self, bag_type: &BagType) -> Option<BagId> {
+        self.ids.get(bag_type).copied()
+    }
+
+    pub fn bag_type(&self, id: BagId) -> &BagType {
+        &self.bag_types[id]
+    }
+
+    pub fn len(&self) -> usize {
+        self.bag_types.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.bag_types.is_empty()
+    }
+
+    pub fn inner(&self, id: BagId) -> &[(BagId, i32)] {
+        &self.contains[id]
+    }
+
+    pub fn outer(&self, id: BagId) -> &[(BagId, i32)] {
+        &self.contained_by[id]
+    }
+
+    /// `start` itself only counts if it's on a cycle
+    fn reachable(&self, start: BagId, edges: Edges) -> Vec<bool> {
+        let mut seen = vec![false; self.len()];
+        let mut queue = VecDeque::from(vec![start]);
+        while let Some(id) = queue.pop_front() {
+            for &(next, _) in edges(self, id) {
+                if !seen[next] {
+                    seen[next] = true;
+                    queue.push_back(next);
+                }
+            }
+        }
+        seen
+    }
+
     pub fn bags_that_contain(&self, bag_type: &BagType) -> HashSet<&BagType> {
-        self.matrix
+        let start = match self.id(bag_type) {
+            Some(id) => id,
+            None => return HashSet::new(),
+        };
+        self.reachable(start, BagGraph::outer)
             .iter()
-            .filter(|(_, contents, _)| bag_type.eq(contents))
-            .fold(HashSet::new(), |mut acc, (bag, ..)| {
-                acc.insert(bag);
-                acc.union(&self.bags_that_contain(bag)).copied().collect()
-            })
+            .enumerate()
+            .filter(|(_, &seen)| seen)
+            .map(|(id, _)| self.bag_type(id))
+            .collect()
+    }
+
+    fn selected(&self, view: &GraphView) -> Vec<bool> {
+        let (bag_type, edges): (_, Edges) = match view {
+            GraphView::Whole => return vec![true; self.len()],
+            GraphView::Inside(bag_type) => (bag_type, BagGraph::inner),
+            GraphView::Outside(bag_type) => (bag_type, BagGraph::outer),
+        };
+        match self.id(bag_type) {
+            Some(id) => {
+                let mut selected = self.reachable(id, edges);
+                selected[id] = true;
+                selected
+            }
+            None => vec![false; self.len()],
+        }
     }
 
-    pub fn contents(&self, bag_type: &BagType) -> i32 {
-        self.matrix
+    fn view(&self, view: &GraphView) -> (Vec<BagId>, Vec<(BagId, BagId, i32)>) {
+        let selected = self.selected(view);
+        let nodes = (0..self.len())
+            .filter(|&id| selected[id])
+            .collect::<Vec<_>>();
+        let edges = nodes
             .iter()
-            .filter(|(bag, ..)| bag_type.eq(bag))
-            .fold(0, |acc, (_, contents, num)| {
-                acc + num * (self.contents(contents) + 1)
+            .flat_map(|&outer| {
+                self.inner(outer)
+                    .iter()
+                    .filter(|(inner, _)| selected[*inner])
+                    .map(move |&(inner, num)| (outer, inner, num))
             })
+            .collect();
+        (nodes, edges)
+    }
+
+    /// edges point from outer bag to inner bag
+    pub fn to_dot(&self, view: &GraphView) -> String {
+        let (nodes, edges) = self.view(view);
+        let mut dot = String::from("digraph bags {\n");
+        for id in nodes {
+            dot.push_str(&format!("    \"{}\";\n", self.bag_type(id)));
+        }
+        for (outer, inner, num) in edges {
+            dot.push_str(&format!(
+                "    \"{}\" -> \"{}\" [label=\"{}\"];\n",
+                self.bag_type(outer),
+                self.bag_type(inner),
+                num
+            ));
+        }
+        dot.push_str("}\n");
+        dot
+    }
+
+    /// edges point from outer bag to inner bag
+    pub fn to_mermaid(&self, view: &GraphView) -> String {
+        let (nodes, edges) = self.view(view);
+        let mut mermaid = String::from("graph TD\n");
+        for id in nodes {
+            mermaid.push_str(&format!("    bag{}[\"{}\"]\n", id, self.bag_type(id)));
+        }
+        for (outer, inner, num) in edges {
+            mermaid.push_str(&format!("    bag{} -->|{}| bag{}\n", outer, num, inner));
+        }
+        mermaid
+    }
+
+    /// `None` if `bag_type` reaches a cycle
+    pub fn contents(&self, bag_type: &BagType) -> Option<i32> {
+        let mut memo = vec![Mark::Unvisited; self.len()];
+        self.id(bag_type)
+            .map_or(Some(0), |id| self.contents_memoized(id, &mut memo))
+    }
+
+    fn contents_memoized(&self, id: BagId, memo: &mut Vec<Mark<i32>>) -> Option<i32> {
+        match memo[id] {
+            Mark::Done(count) => return Some(count),
+            Mark::InProgress => return None,
+            Mark::Unvisited => memo[id] = Mark::InProgress,
+        }
+        let mut count = 0;
+        for &(inner, num) in &self.contains[id] {
+            count += num * (self.contents_memoized(inner, memo)? + 1);
+        }
+        memo[id] = Mark::Done(count);
+        Some(count)
+    }
+
+    /// the quantities of all the paths add up to how many `inner` bags one `outer` bag holds
+    pub fn containment_paths(&self, outer: &BagType, inner: &BagType) -> Vec<ContainmentPath<'_>> {
+        let (outer, inner) = match (self.id(outer), self.id(inner)) {
+            (Some(outer), Some(inner)) => (outer, inner),
+            _ => return Vec::new(),
+        };
+        let reaches_inner = self.reachable(inner, BagGraph::outer);
+        let mut paths = Vec::new();
+        let mut path = vec![outer];
+        self.collect_paths(inner, &reaches_inner, &mut path, 1, &mut paths);
+        paths
+    }
+
+    fn collect_paths<'a>(
+        &'a self,
+        goal: BagId,
+        reaches_goal: &[bool],
+        path: &mut Vec<BagId>,
+        quantity: i32,
+        paths: &mut Vec<ContainmentPath<'a>>,
+    ) {
+        let id = *path.last().unwrap();
+        for &(next, num) in self.inner(id) {
+            if path.contains(&next) {
+                continue;
+            }
+            if next == goal {
+                let mut bags = path.iter().map(|&id| self.bag_type(id)).collect::<Vec<_>>();
+                bags.push(self.bag_type(goal));
+                paths.push(ContainmentPath {
+                    bags,
+                    quantity: quantity * num,
+                });
+            } else if reaches_goal[next] {
+                path.push(next);
+                self.collect_paths(goal, reaches_goal, path, quantity * num, paths);
+                path.pop();
+            }
+        }
+    }
+
+    /// `None` if `outer` holds nothing or reaches a cycle
+    pub fn heaviest_path(&self, outer: &BagType) -> Option<ContainmentPath<'_>> {
+        let mut id = self.id(outer)?;
+        let mut memo = vec![Mark::Unvisited; self.len()];
+        let (quantity, mut next) = self.heaviest_memoized(id, &mut memo)?;
+        let mut bags = vec![self.bag_type(id)];
+        while let Some(inner) = next {
+            id = inner;
+            bags.push(self.bag_type(id));
+            next = match memo[id] {
+                Mark::Done((_, next)) => next,
+                _ => None,
+            };
+        }
+        if bags.len() > 1 {
+            Some(ContainmentPath { bags, quantity })
+        } else {
+            None
+        }
+    }
+
+    fn heaviest_memoized(
+        &self,
+        id: BagId,
+        memo: &mut Vec<Mark<(i32, Option<BagId>)>>,
+    ) -> Option<(i32, Option<BagId>)> {
+        match memo[id] {
+            Mark::Done(best) => return Some(best),
+            Mark::InProgress => return None,
+            Mark::Unvisited => memo[id] = Mark::InProgress,
+        }
+        let mut best = (1, None);
+        for &(inner, num) in &self.contains[id] {
+            let quantity = num * self.heaviest_memoized(inner, memo)?.0;
+            if best.1.is_none() || quantity >= best.0 {
+                best = (quantity, Some(inner));
+            }
+        }
+        memo[id] = Mark::Done(best);
+        Some(best)
+    }
+}
+
+#[derive(Debug, PartialEq)]
+pub struct ContainmentPath<'a> {
+    /// from the outermost bag to the innermost
+    pub bags: Vec<&'a BagType>,
+    /// how many of the innermost bag the outermost one holds by way of this path
+    pub quantity: i32,
+}
+
+impl fmt::Display for ContainmentPath<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "{} ({})",
+            self.bags
+                .iter()
+                .map(ToString::to_string)
+                .collect::<Vec<_>>()
+                .join(" -> "),
+            self.quantity
+        )
+    }
+}
+
+impl<'a> FromIterator<&'a Bag> for BagGraph {
+    fn from_iter<T: IntoIterator<Item = &'a Bag>>(iter: T) -> Self {
+        let mut graph = BagGraph::new();
+        for bag in iter {
+            graph.add(bag)
+        }
+        graph
     }
 }
 
 #[cfg(test)]
 mod test {
-    use crate::day07::{Bag, HashMatrix};
+    use crate::day07::{Bag, BagGraph, BagRuleError, BagType, GraphView};
     use crate::loader::file_to_vec;
+    use std::fs::read_to_string;
 
     #[test]
     fn test_parse() {
@@ -126,13 +532,13 @@ mod test {
     #[test]
     fn test_small() {
         let input = file_to_vec::<Bag>("inputs/day07small.txt");
-        let mut hash_matrix = HashMatrix::new();
+        let mut bag_graph = BagGraph::new();
         let a = timed!(
             {
                 for bag in input.iter() {
-                    hash_matrix.add(&bag)
+                    bag_graph.add(&bag)
                 }
-                hash_matrix
+                bag_graph
                     .bags_that_contain(&"shiny gold".parse().unwrap())
                     .len()
             },
@@ -144,13 +550,13 @@ mod test {
     #[test]
     fn test_large() {
         let input = file_to_vec::<Bag>("inputs/day07.txt");
-        let mut hash_matrix = HashMatrix::new();
+        let mut bag_graph = BagGraph::new();
         let a = timed!(
             {
                 for bag in input.iter() {
-                    hash_matrix.add(&bag)
+                    bag_graph.add(&bag)
                 }
-                hash_matrix
+                bag_graph
                     .bags_that_contain(&"shiny gold".parse().unwrap())
                     .len()
             },
@@ -162,33 +568,250 @@ mod test {
     #[test]
     fn test_small_p2() {
         let input = file_to_vec::<Bag>("inputs/day07small2.txt");
-        let mut hash_matrix = HashMatrix::new();
+        let mut bag_graph = BagGraph::new();
         let a = timed!(
             {
                 for bag in input.iter() {
-                    hash_matrix.add(&bag)
+                    bag_graph.add(&bag)
                 }
-                hash_matrix.contents(&"shiny gold".parse().unwrap())
+                bag_graph.contents(&"shiny gold".parse().unwrap())
             },
             "test_small_p2"
         );
 
-        assert_eq!(126, a);
+        assert_eq!(Some(126), a);
     }
 
     #[test]
     fn test_large_p2() {
         let input = file_to_vec::<Bag>("inputs/day07.txt");
-        let mut hash_matrix = HashMatrix::new();
+        let mut bag_graph = BagGraph::new();
         let a = timed!(
             {
                 for bag in input.iter() {
-                    hash_matrix.add(&bag)
+                    bag_graph.add(&bag)
                 }
-                hash_matrix.contents(&"shiny gold".parse().unwrap())
+                bag_graph.contents(&"shiny gold".parse().unwrap())
             },
             "test_large_p2"
         );
-        assert_eq!(34862, a);
+        assert_eq!(Some(34862), a);
+    }
+
+    #[test]
+    fn test_reverse_edges() {
+        let input = file_to_vec::<Bag>("inputs/day07small.txt");
+        let bag_graph = input.iter().collect::<BagGraph>();
+        let shiny_gold = bag_graph.id(&"shiny gold".parse().unwrap()).unwrap();
+        let mut outer = bag_graph
+            .outer(shiny_gold)
+            .iter()
+            .map(|&(id, num)| (bag_graph.bag_type(id).to_owned(), num))
+            .collect::<Vec<_>>();
+        outer.sort_by_key(|(_, num)| *num);
+        assert_eq!(
+            outer,
+            vec![
+                ("bright white".parse().unwrap(), 1),
+                ("muted yellow".parse().unwrap(), 2)
+            ]
+        );
+        assert_eq!(bag_graph.contents(&"faded blue".parse().unwrap()), Some(0));
+        assert_eq!(bag_graph.contents(&"not a bag".parse().unwrap()), Some(0));
+    }
+
+    fn parse_rules(rules: &[&str]) -> Vec<Bag> {
+        rules.iter().map(|s| s.parse().unwrap()).collect()
+    }
+
+    #[test]
+    fn test_validate_large() {
+        let input = file_to_vec::<Bag>("inputs/day07.txt");
+        assert!(BagGraph::build(&input).is_ok());
+    }
+
+    #[test]
+    fn test_validate_cycle() {
+        let input = parse_rules(&[
+            "light red bags contain 1 bright white bag.",
+            "bright white bags contain 2 muted yellow bags.",
+            "muted yellow bags contain 1 light red bag, 1 faded blue bag.",
+            "faded blue bags contain no other bags.",
+        ]);
+        let errors = BagGraph::build(&input).unwrap_err();
+        assert_eq!(
+            errors,
+            vec![BagRuleError::Cycle(vec![
+                "light red".parse().unwrap(),
+                "bright white".parse().unwrap(),
+                "muted yellow".parse().unwrap(),
+            ])]
+        );
+        assert_eq!(
+            errors[0].to_string(),
+            "cycle: light red -> bright white -> muted yellow -> light red"
+        );
+    }
+
+    #[test]
+    fn test_validate_self_containing_and_undeclared() {
+        let input = parse_rules(&["light red bags contain 1 light red bag, 2 faded blue bags."]);
+        let errors = BagGraph::build(&input).unwrap_err();
+        assert_eq!(
+            errors,
+            vec![
+                BagRuleError::SelfContaining("light red".parse().unwrap()),
+                BagRuleError::Undeclared {
+                    bag_type: "faded blue".parse().unwrap(),
+                    referenced_by: "light red".parse().unwrap(),
+                },
+            ]
+        );
+    }
+
+    #[test]
+    fn test_to_dot() {
+        let input = file_to_vec::<Bag>("inputs/day07small.txt");
+        let bag_graph = BagGraph::build(&input).unwrap();
+        let shiny_gold = "shiny gold".parse().unwrap();
+        assert_eq!(
+            bag_graph.to_dot(&GraphView::Outside(&shiny_gold)),
+            "digraph bags {
+    \"light red\";
+    \"bright white\";
+    \"muted yellow\";
+    \"dark orange\";
+    \"shiny gold\";
+    \"light red\" -> \"bright white\" [label=\"1\"];
+    \"light red\" -> \"muted yellow\" [label=\"2\"];
+    \"bright white\" -> \"shiny gold\" [label=\"1\"];
+    \"muted yellow\" -> \"shiny gold\" [label=\"2\"];
+    \"dark orange\" -> \"bright white\" [label=\"3\"];
+    \"dark orange\" -> \"muted yellow\" [label=\"4\"];
+}
+"
+        );
+        assert_eq!(
+            bag_graph.to_dot(&GraphView::Whole).matches(" -> ").count(),
+            13
+        );
+    }
+
+    #[test]
+    fn test_to_mermaid() {
+        let input = file_to_vec::<Bag>("inputs/day07small.txt");
+        let bag_graph = BagGraph::build(&input).unwrap();
+        let dark_olive = "dark olive".parse().unwrap();
+        assert_eq!(
+            bag_graph.to_mermaid(&GraphView::Inside(&dark_olive)),
+            "graph TD
+    bag5[\"faded blue\"]
+    bag6[\"dark olive\"]
+    bag8[\"dotted black\"]
+    bag6 -->|3| bag5
+    bag6 -->|4| bag8
+"
+        );
+    }
+
+    #[test]
+    fn test_containment_paths() {
+        let input = file_to_vec::<Bag>("inputs/day07small.txt");
+        let bag_graph = BagGraph::build(&input).unwrap();
+        let paths = bag_graph.containment_paths(
+            &"light red".parse().unwrap(),
+            &"shiny gold".parse().unwrap(),
+        );
+        assert_eq!(
+            paths.iter().map(ToString::to_string).collect::<Vec<_>>(),
+            vec![
+                "light red -> bright white -> shiny gold (1)",
+                "light red -> muted yellow -> shiny gold (4)"
+            ]
+        );
+        assert!(bag_graph
+            .containment_paths(
+                &"shiny gold".parse().unwrap(),
+                &"light red".parse().unwrap()
+            )
+            .is_empty());
+    }
+
+    #[test]
+    fn test_containment_paths_sum_to_contents() {
+        let input = file_to_vec::<Bag>("inputs/day07.txt");
+        let bag_graph = BagGraph::build(&input).unwrap();
+        let shiny_gold = "shiny gold".parse().unwrap();
+        let contents = (0..bag_graph.len())
+            .map(|id| bag_graph.bag_type(id))
+            .flat_map(|inner| bag_graph.containment_paths(&shiny_gold, inner))
+            .map(|path| path.quantity)
+            .sum::<i32>();
+        assert_eq!(Some(contents), bag_graph.contents(&shiny_gold));
+    }
+
+    #[test]
+    fn test_heaviest_path() {
+        let input = file_to_vec::<Bag>("inputs/day07small2.txt");
+        let bag_graph = BagGraph::build(&input).unwrap();
+        let path = bag_graph
+            .heaviest_path(&"shiny gold".parse().unwrap())
+            .unwrap();
+        assert_eq!(path.bags.len(), 7);
+        assert_eq!(path.quantity, 64);
+        assert_eq!(
+            bag_graph.heaviest_path(&"dark violet".parse().unwrap()),
+            None
+        );
+    }
+
+    #[test]
+    fn test_cyclic_graph_without_validation() {
+        let mut bag_graph = BagGraph::new();
+        for bag in parse_rules(&[
+            "light red bags contain 1 bright white bag.",
+            "bright white bags contain 1 light red bag.",
+            "dark orange bags contain 2 faded blue bags.",
+            "faded blue bags contain no other bags.",
+        ]) {
+            bag_graph.add(&bag);
+        }
+        let light_red = "light red".parse().unwrap();
+        assert_eq!(bag_graph.contents(&light_red), None);
+        assert_eq!(bag_graph.heaviest_path(&light_red), None);
+        let dark_orange = "dark orange".parse().unwrap();
+        assert_eq!(bag_graph.contents(&dark_orange), Some(2));
+        assert_eq!(bag_graph.heaviest_path(&dark_orange).unwrap().quantity, 2);
+    }
+
+    #[test]
+    fn test_round_trip() {
+        let input = read_to_string("inputs/day07.txt").unwrap();
+        for line in input.lines() {
+            assert_eq!(line.parse::<Bag>().unwrap().to_string(), line);
+        }
+    }
+
+    #[test]
+    fn test_multi_word_adjective() {
+        let bag = "very light red bags contain 1 pale shiny gold bag, 3 faded blue bags."
+            .parse::<Bag>()
+            .unwrap();
+        assert_eq!(
+            bag.to_string(),
+            "very light red bags contain 1 pale shiny gold bag, 3 faded blue bags."
+        );
+        assert_eq!(
+            "pale shiny gold bag".parse::<BagType>(),
+            "pale shiny gold".parse()
+        );
+        assert!("gold".parse::<BagType>().is_err());
+        assert_eq!(
+            "faded blue bags contain no other bags."
+                .parse::<Bag>()
+                .unwrap()
+                .to_string(),
+            "faded blue bags contain no other bags."
+        );
     }
 }
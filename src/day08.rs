@@ -1,192 +1,944 @@
 use core::fmt;
-use std::convert::TryInto;
+use std::collections::{HashMap, HashSet};
+use std::convert::TryFrom;
+use std::io::{self, BufRead, Write};
+use std::mem;
 use std::str::FromStr;
 
-#[derive(Debug, Copy, Clone)]
-enum Operation {
-    Acc,
-    Jmp,
-    Nop,
-}
+const REGISTERS: usize = 26;
+
+pub const ACCUMULATOR: Register = Register(0);
+
+/// how far [`Program::run`] goes without [`Program::with_step_limit`] when some jump depends on
+/// the registers, since every state it passes through is kept
+pub const DEFAULT_STEP_LIMIT: usize = 100_000;
 
-impl fmt::Display for Operation {
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub struct Register(usize);
+
+impl fmt::Display for Register {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
-        write!(
-            f,
-            "{}",
-            match self {
-                Operation::Acc => {
-                    "acc"
-                }
-                Operation::Jmp => {
-                    "jmp"
-                }
-                Operation::Nop => {
-                    "nop"
-                }
-            }
-        )
+        write!(f, "{}", (b'a' + self.0 as u8) as char)
     }
 }
 
-impl FromStr for Operation {
+impl FromStr for Register {
     type Err = ();
 
     fn from_str(s: &str) -> Result<Self, Self::Err> {
-        match s {
-            "nop" => Ok(Operation::Nop),
-            "acc" => Ok(Operation::Acc),
-            "jmp" => Ok(Operation::Jmp),
+        match s.as_bytes() {
+            [c @ b'a'..=b'z'] => Ok(Register((c - b'a') as usize)),
             _ => Err(()),
         }
     }
 }
 
-#[derive(Debug, Copy, Clone)]
-pub struct Instruction {
-    operation: Operation,
-    argument: i64,
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub enum Operand {
+    Value(i64),
+    Register(Register),
 }
 
-impl fmt::Display for Instruction {
+impl fmt::Display for Operand {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
-        write!(f, "{}, {}", self.operation, self.argument)
+        match self {
+            Operand::Value(value) => write!(f, "{:+}", value),
+            Operand::Register(register) => write!(f, "{}", register),
+        }
     }
 }
 
-impl FromStr for Instruction {
+impl FromStr for Operand {
     type Err = ();
 
     fn from_str(s: &str) -> Result<Self, Self::Err> {
-        let (op, arg) = s
-            .split_once(" ")
-            .unwrap_or_else(|| panic!("called split_once(\" \") on {}", s));
-        Ok(Instruction {
-            operation: op
+        s.parse()
+            .map(Operand::Value)
+            .or_else(|_| s.parse().map(Operand::Register))
+    }
+}
+
+/// where control can go after an instruction, before knowing what's in the registers
+#[derive(Debug, Copy, Clone, PartialEq)]
+enum Flow {
+    Next,
+    Jump(Operand),
+    /// either the next instruction or the jump
+    Branch(Operand),
+    Halt,
+}
+
+/// Each entry gives the mnemonic, the operands in order, where control can go and what running
+/// it does, so a new opcode only needs a new entry.
+macro_rules! instruction_set {
+    ($($variant:ident $mnemonic:literal { $($operand:ident: $ty:ty),* } -> $flow:expr => |$program:ident| $body:block)*) => {
+        #[derive(Debug, Copy, Clone, PartialEq)]
+        pub enum Instruction {
+            $($variant { $($operand: $ty),* }),*
+        }
+
+        impl Instruction {
+            pub const MNEMONICS: &'static [&'static str] = &[$($mnemonic),*];
+
+            pub fn mnemonic(&self) -> &'static str {
+                match self {
+                    $(Instruction::$variant { .. } => $mnemonic),*
+                }
+            }
+
+            #[allow(unused_variables)]
+            fn flow(self) -> Flow {
+                match self {
+                    $(Instruction::$variant { $($operand),* } => $flow),*
+                }
+            }
+
+            #[allow(unused_variables)]
+            fn execute(self, program: &mut Program) {
+                match self {
+                    $(Instruction::$variant { $($operand),* } => {
+                        let $program = program;
+                        $body
+                    })*
+                }
+            }
+        }
+
+        impl fmt::Display for Instruction {
+            fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+                write!(f, "{}", self.mnemonic())?;
+                match self {
+                    $(Instruction::$variant { $($operand),* } => {
+                        $(write!(f, " {}", $operand)?;)*
+                    })*
+                }
+                Ok(())
+            }
+        }
+
+        impl FromStr for Instruction {
+            type Err = ();
+
+            fn from_str(s: &str) -> Result<Self, Self::Err> {
+                let mut words = s.split_whitespace();
+                let instruction = match words.next().ok_or(())? {
+                    $($mnemonic => Instruction::$variant {
+                        $($operand: words.next().ok_or(())?.parse().map_err(|_| ())?),*
+                    },)*
+                    _ => return Err(()),
+                };
+                match words.next() {
+                    Some(_) => Err(()),
+                    None => Ok(instruction),
+                }
+            }
+        }
+    };
+}
+
+instruction_set! {
+    Acc "acc" { value: Operand } -> Flow::Next => |program| {
+        let sum = program.get_register(ACCUMULATOR).wrapping_add(program.read(value));
+        *program.register_mut(ACCUMULATOR) = sum;
+        program.advance();
+    }
+    Jmp "jmp" { offset: Operand } -> Flow::Jump(offset) => |program| {
+        program.jump(program.read(offset));
+    }
+    Nop "nop" { value: Operand } -> Flow::Next => |program| {
+        program.advance();
+    }
+    Set "set" { register: Register, value: Operand } -> Flow::Next => |program| {
+        *program.register_mut(register) = program.read(value);
+        program.advance();
+    }
+    Add "add" { register: Register, value: Operand } -> Flow::Next => |program| {
+        let sum = program.get_register(register).wrapping_add(program.read(value));
+        *program.register_mut(register) = sum;
+        program.advance();
+    }
+    Mul "mul" { register: Register, value: Operand } -> Flow::Next => |program| {
+        let product = program.get_register(register).wrapping_mul(program.read(value));
+        *program.register_mut(register) = product;
+        program.advance();
+    }
+    Jz "jz" { register: Register, offset: Operand } -> Flow::Branch(offset) => |program| {
+        match program.get_register(register) {
+            0 => program.jump(program.read(offset)),
+            _ => program.advance(),
+        }
+    }
+    Jnz "jnz" { register: Register, offset: Operand } -> Flow::Branch(offset) => |program| {
+        match program.get_register(register) {
+            0 => program.advance(),
+            _ => program.jump(program.read(offset)),
+        }
+    }
+    Out "out" { value: Operand } -> Flow::Next => |program| {
+        let value = program.read(value);
+        program.output.push(value);
+        program.advance();
+    }
+    Hlt "hlt" {} -> Flow::Halt => |program| {
+        program.halted = true;
+    }
+}
+
+fn swap_jmp_nop(instruction: Instruction) -> Option<Instruction> {
+    match instruction {
+        Instruction::Jmp { offset } => Some(Instruction::Nop { value: offset }),
+        Instruction::Nop { value } => Some(Instruction::Jmp { offset: value }),
+        _ => None,
+    }
+}
+
+/// Lines are counted from 1. Unknown names show up as an invalid instruction once labels and
+/// constants have been replaced.
+#[derive(Debug, PartialEq)]
+pub enum AssembleError {
+    InvalidInstruction { line: usize, text: String },
+    DuplicateName { line: usize, name: String },
+    ReservedName { line: usize, name: String },
+    EmptyName { line: usize },
+    InvalidName { line: usize, name: String },
+    InvalidConstant { line: usize, text: String },
+}
+
+impl fmt::Display for AssembleError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            AssembleError::InvalidInstruction { line, text } => {
+                write!(f, "line {}: invalid instruction \"{}\"", line, text)
+            }
+            AssembleError::DuplicateName { line, name } => {
+                write!(f, "line {}: \"{}\" is already defined", line, name)
+            }
+            AssembleError::ReservedName { line, name } => {
+                write!(f, "line {}: \"{}\" is reserved", line, name)
+            }
+            AssembleError::EmptyName { line } => write!(f, "line {}: missing name", line),
+            AssembleError::InvalidName { line, name } => {
+                write!(f, "line {}: \"{}\" is not a valid name", line, name)
+            }
+            AssembleError::InvalidConstant { line, text } => {
+                write!(f, "line {}: invalid constant \"{}\"", line, text)
+            }
+        }
+    }
+}
+
+enum Name {
+    Constant(i64),
+    /// the index of the instruction after the label
+    Label(usize),
+}
+
+/// `[A-Za-z_][A-Za-z0-9_]*`, so a name is always one word that can't be read as a number
+fn is_identifier(name: &str) -> bool {
+    let mut chars = name.chars();
+    matches!(chars.next(), Some(c) if c.is_ascii_alphabetic() || c == '_')
+        && chars.all(|c| c.is_ascii_alphanumeric() || c == '_')
+}
+
+/// Assembles source with labels, constants and comments into instructions. A label is a name
+/// followed by `:` at the start of a line and becomes the relative offset to the instruction
+/// after it, a constant is defined as `name = 5`, and everything after a `;` is a comment.
+pub fn assemble(source: &str) -> Result<Vec<Instruction>, AssembleError> {
+    let mut names = HashMap::new();
+    let mut lines = Vec::new();
+    for (line, text) in source.lines().enumerate() {
+        let line = line + 1;
+        let mut text = text.split(';').next().unwrap().trim();
+        let mut define = |name: &str, value: Name| {
+            if name.is_empty() {
+                return Err(AssembleError::EmptyName { line });
+            }
+            if !is_identifier(name) {
+                return Err(AssembleError::InvalidName {
+                    line,
+                    name: String::from(name),
+                });
+            }
+            if name.parse::<Register>().is_ok() || Instruction::MNEMONICS.contains(&name) {
+                return Err(AssembleError::ReservedName {
+                    line,
+                    name: String::from(name),
+                });
+            }
+            match names.insert(String::from(name), value) {
+                Some(_) => Err(AssembleError::DuplicateName {
+                    line,
+                    name: String::from(name),
+                }),
+                None => Ok(()),
+            }
+        };
+        if let Some((name, rest)) = text.split_once(':') {
+            define(name.trim(), Name::Label(lines.len()))?;
+            text = rest.trim();
+        }
+        if let Some((name, value)) = text.split_once('=') {
+            let value = value
+                .trim()
                 .parse()
-                .unwrap_or_else(|_| panic!("called split_once(\" \") on {}", s)),
-            argument: arg
+                .map_err(|_| AssembleError::InvalidConstant {
+                    line,
+                    text: String::from(text),
+                })?;
+            define(name.trim(), Name::Constant(value))?;
+        } else if !text.is_empty() {
+            lines.push((line, text));
+        }
+    }
+    lines
+        .into_iter()
+        .enumerate()
+        .map(|(pc, (line, text))| {
+            let resolved = text
+                .split_whitespace()
+                .map(|word| match names.get(word) {
+                    Some(Name::Constant(value)) => format!("{:+}", value),
+                    Some(Name::Label(target)) => format!("{:+}", *target as i64 - pc as i64),
+                    None => String::from(word),
+                })
+                .collect::<Vec<_>>()
+                .join(" ");
+            resolved
                 .parse()
-                .unwrap_or_else(|_| panic!("attempted to turn {} into a Operation", op)),
+                .map_err(|_| AssembleError::InvalidInstruction {
+                    line,
+                    text: String::from(text),
+                })
         })
+        .collect()
+}
+
+/// The output assembles back into the same instructions.
+pub fn disassemble(instructions: &[Instruction]) -> String {
+    let mut out = String::new();
+    for (pc, instruction) in instructions.iter().enumerate() {
+        let offset = match instruction.flow() {
+            Flow::Jump(offset) | Flow::Branch(offset) => Some(offset),
+            Flow::Next | Flow::Halt => None,
+        };
+        let line = format!("{:<16}; {}", instruction.to_string(), pc);
+        out.push_str(line.as_str());
+        if let Some(Operand::Value(offset)) = offset {
+            out.push_str(&format!(" -> {}", (pc as i64).wrapping_add(offset)));
+        }
+        out.push('\n');
     }
+    out
+}
+
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub enum Successor {
+    Instruction(usize),
+    /// just past the last instruction, or `hlt`
+    Exit,
+    OutOfBounds(i64),
+    Unknown,
+}
+
+fn successors(pc: usize, instruction: Instruction, len: usize) -> Vec<Successor> {
+    let to = |target: i64| {
+        if target == len as i64 {
+            Successor::Exit
+        } else if (0..len as i64).contains(&target) {
+            Successor::Instruction(target as usize)
+        } else {
+            Successor::OutOfBounds(target)
+        }
+    };
+    let next = to(pc as i64 + 1);
+    match instruction.flow() {
+        Flow::Next => vec![next],
+        Flow::Jump(Operand::Value(offset)) => vec![to((pc as i64).wrapping_add(offset))],
+        Flow::Jump(Operand::Register(_)) => vec![Successor::Unknown],
+        Flow::Branch(Operand::Value(offset)) => vec![next, to((pc as i64).wrapping_add(offset))],
+        Flow::Branch(Operand::Register(_)) => vec![next, Successor::Unknown],
+        Flow::Halt => vec![Successor::Exit],
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct BasicBlock {
+    pub start: usize,
+    pub end: usize,
+    /// after the last instruction, every target here is the start of some block
+    pub successors: Vec<Successor>,
+}
+
+#[derive(Debug)]
+pub struct ControlFlowGraph {
+    blocks: Vec<BasicBlock>,
+    block_of: Vec<usize>,
+}
+
+impl ControlFlowGraph {
+    pub fn new(instructions: &[Instruction]) -> ControlFlowGraph {
+        let len = instructions.len();
+        let mut leader = vec![false; len];
+        if len > 0 {
+            leader[0] = true;
+        }
+        for (pc, &instruction) in instructions.iter().enumerate() {
+            let successors = successors(pc, instruction, len);
+            if successors != [Successor::Instruction(pc + 1)] {
+                for successor in successors
+                    .into_iter()
+                    .chain(Some(Successor::Instruction(pc + 1)))
+                {
+                    if let Successor::Instruction(target) = successor {
+                        if target < len {
+                            leader[target] = true;
+                        }
+                    }
+                }
+            }
+        }
+        let mut blocks = Vec::new();
+        let mut block_of = vec![0; len];
+        for pc in 0..len {
+            if leader[pc] {
+                blocks.push(BasicBlock {
+                    start: pc,
+                    end: pc,
+                    successors: Vec::new(),
+                });
+            }
+            let block = blocks.last_mut().unwrap();
+            block.end = pc + 1;
+            block_of[pc] = blocks.len() - 1;
+        }
+        for block in &mut blocks {
+            block.successors = successors(block.end - 1, instructions[block.end - 1], len);
+        }
+        ControlFlowGraph { blocks, block_of }
+    }
+
+    pub fn blocks(&self) -> &[BasicBlock] {
+        &self.blocks
+    }
+
+    pub fn block_containing(&self, pc: usize) -> usize {
+        self.block_of[pc]
+    }
+
+    fn next_blocks(&self, block: usize) -> impl Iterator<Item = usize> + '_ {
+        self.blocks[block]
+            .successors
+            .iter()
+            .filter_map(move |successor| match successor {
+                Successor::Instruction(pc) => Some(self.block_of[*pc]),
+                _ => None,
+            })
+    }
+
+    fn reachable_blocks(&self) -> Vec<bool> {
+        let mut reachable = vec![false; self.blocks.len()];
+        let mut stack = Vec::new();
+        if !self.blocks.is_empty() {
+            reachable[0] = true;
+            stack.push(0);
+        }
+        while let Some(block) = stack.pop() {
+            if self.blocks[block].successors.contains(&Successor::Unknown) {
+                // a jump by a register could land on any instruction
+                return vec![true; self.blocks.len()];
+            }
+            for next in self.next_blocks(block) {
+                if !mem::replace(&mut reachable[next], true) {
+                    stack.push(next);
+                }
+            }
+        }
+        reachable
+    }
+
+    /// assumes a reachable jump by a register can reach anything
+    pub fn unreachable_instructions(&self) -> Vec<usize> {
+        let reachable = self.reachable_blocks();
+        (0..self.block_of.len())
+            .filter(|&pc| !reachable[self.block_of[pc]])
+            .collect()
+    }
+
+    /// Found with Kosaraju's algorithm over the blocks, jumps by a register are left out.
+    pub fn instructions_on_cycles(&self) -> Vec<usize> {
+        let n = self.blocks.len();
+        let mut finished = Vec::with_capacity(n);
+        let mut visited = vec![false; n];
+        for root in 0..n {
+            if mem::replace(&mut visited[root], true) {
+                continue;
+            }
+            let mut stack = vec![(root, self.next_blocks(root).collect::<Vec<_>>())];
+            while let Some((block, next)) = stack.last_mut() {
+                match next.pop() {
+                    Some(next) if !visited[next] => {
+                        visited[next] = true;
+                        stack.push((next, self.next_blocks(next).collect()));
+                    }
+                    Some(_) => {}
+                    None => {
+                        finished.push(*block);
+                        stack.pop();
+                    }
+                }
+            }
+        }
+        let mut previous_blocks = vec![Vec::new(); n];
+        for block in 0..n {
+            for next in self.next_blocks(block) {
+                previous_blocks[next].push(block);
+            }
+        }
+        let mut component = vec![None; n];
+        let mut component_sizes = Vec::new();
+        for &root in finished.iter().rev() {
+            if component[root].is_some() {
+                continue;
+            }
+            let id = component_sizes.len();
+            component_sizes.push(0);
+            component[root] = Some(id);
+            let mut stack = vec![root];
+            while let Some(block) = stack.pop() {
+                component_sizes[id] += 1;
+                for &previous in &previous_blocks[block] {
+                    if component[previous].is_none() {
+                        component[previous] = Some(id);
+                        stack.push(previous);
+                    }
+                }
+            }
+        }
+        (0..self.block_of.len())
+            .filter(|&pc| {
+                let block = self.block_of[pc];
+                component_sizes[component[block].unwrap()] > 1
+                    || self.next_blocks(block).any(|next| next == block)
+            })
+            .collect()
+    }
+
+    /// Conditional jumps are assumed to go either way and a jump by a register anywhere, so for
+    /// programs with neither this is exactly whether the program halts.
+    pub fn can_terminate(&self) -> bool {
+        if self.blocks.is_empty() {
+            return true;
+        }
+        let reachable = self.reachable_blocks();
+        self.blocks
+            .iter()
+            .enumerate()
+            .filter(|(block, _)| reachable[*block])
+            .flat_map(|(_, block)| block.successors.iter())
+            .any(|successor| matches!(successor, Successor::Exit | Successor::Unknown))
+    }
+}
+
+struct LoopDetector {
+    static_control_flow: bool,
+    executed: Vec<bool>,
+    seen: HashSet<(usize, [i64; REGISTERS])>,
+}
+
+impl LoopDetector {
+    fn new(program: &Program) -> LoopDetector {
+        LoopDetector {
+            static_control_flow: program.has_static_control_flow(),
+            executed: vec![false; program.instructions.len()],
+            seen: HashSet::new(),
+        }
+    }
+
+    fn repeated(&mut self, pc: usize, registers: [i64; REGISTERS]) -> bool {
+        if self.static_control_flow {
+            mem::replace(&mut self.executed[pc], true)
+        } else {
+            !self.seen.insert((pc, registers))
+        }
+    }
+}
+
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub enum RunOutcome {
+    Halted,
+    InfiniteLoop { pc: usize, acc: i64 },
+    OutOfBounds { pc: i64 },
+    StepLimit,
 }
 
 #[derive(Debug)]
 pub struct Program {
-    accumulator: i64,
-    program_counter: usize,
+    registers: [i64; REGISTERS],
+    program_counter: i64,
+    halted: bool,
+    output: Vec<i64>,
+    step_limit: Option<usize>,
     instructions: Vec<Instruction>,
 }
 
 impl Program {
     pub fn new(instructions: Vec<Instruction>) -> Program {
         Program {
-            accumulator: 0,
+            registers: [0; REGISTERS],
             program_counter: 0,
+            halted: false,
+            output: Vec::new(),
+            step_limit: None,
             instructions,
         }
     }
 
+    pub fn with_step_limit(mut self, limit: usize) -> Program {
+        self.step_limit = Some(limit);
+        self
+    }
+
     pub fn get_acc(&self) -> i64 {
-        self.accumulator
+        self.get_register(ACCUMULATOR)
     }
 
-    fn fetch(&self) -> Option<&Instruction> {
-        self.instructions.get(self.program_counter as usize)
+    pub fn get_register(&self, register: Register) -> i64 {
+        self.registers[register.0]
     }
 
-    fn execute(&mut self, instruction: Instruction) {
-        match instruction.operation {
-            Operation::Acc => {
-                self.accumulator += instruction.argument;
-                self.program_counter += 1;
-            }
-            Operation::Jmp => {
-                self.program_counter = (self.program_counter as i64 + instruction.argument)
-                    .try_into()
-                    .unwrap()
+    pub fn get_output(&self) -> &[i64] {
+        &self.output
+    }
+
+    pub fn is_halted(&self) -> bool {
+        self.halted
+    }
+
+    fn read(&self, operand: Operand) -> i64 {
+        match operand {
+            Operand::Value(value) => value,
+            Operand::Register(register) => self.registers[register.0],
+        }
+    }
+
+    fn register_mut(&mut self, register: Register) -> &mut i64 {
+        &mut self.registers[register.0]
+    }
+
+    fn advance(&mut self) {
+        self.program_counter += 1;
+    }
+
+    fn jump(&mut self, offset: i64) {
+        // a wrapped program counter is just out of bounds
+        self.program_counter = self.program_counter.wrapping_add(offset);
+    }
+
+    fn fetch(&self) -> Result<usize, RunOutcome> {
+        if self.halted || self.program_counter == self.instructions.len() as i64 {
+            return Err(RunOutcome::Halted);
+        }
+        usize::try_from(self.program_counter)
+            .ok()
+            .filter(|&pc| pc < self.instructions.len())
+            .ok_or(RunOutcome::OutOfBounds {
+                pc: self.program_counter,
+            })
+    }
+
+    fn has_static_control_flow(&self) -> bool {
+        self.instructions
+            .iter()
+            .all(|instruction| match instruction.flow() {
+                Flow::Next | Flow::Halt | Flow::Jump(Operand::Value(_)) => true,
+                Flow::Jump(Operand::Register(_)) | Flow::Branch(_) => false,
+            })
+    }
+
+    fn step(&mut self) -> Result<(usize, Instruction), RunOutcome> {
+        let pc = self.fetch()?;
+        let instruction = self.instructions[pc];
+        instruction.execute(self);
+        Ok((pc, instruction))
+    }
+
+    /// With every jump fixed, running an instruction twice means the program never stops,
+    /// otherwise it takes the whole set of registers repeating at the same instruction. Those
+    /// programs stop after [`DEFAULT_STEP_LIMIT`] steps unless given a limit of their own.
+    pub fn run(&mut self) -> RunOutcome {
+        let mut loop_detector = LoopDetector::new(self);
+        let step_limit = match self.step_limit {
+            None if !loop_detector.static_control_flow => Some(DEFAULT_STEP_LIMIT),
+            step_limit => step_limit,
+        };
+        let mut steps = 0;
+        loop {
+            let pc = match self.fetch() {
+                Ok(pc) => pc,
+                Err(outcome) => return outcome,
+            };
+            if loop_detector.repeated(pc, self.registers) {
+                return RunOutcome::InfiniteLoop {
+                    pc,
+                    acc: self.get_acc(),
+                };
             }
-            Operation::Nop => {
-                self.program_counter += 1;
+            if Some(steps) == step_limit {
+                return RunOutcome::StepLimit;
             }
+            steps += 1;
+            self.instructions[pc].execute(self);
         }
     }
 
-    fn step(&mut self) -> Option<()> {
-        if let Some(&instruction) = self.fetch() {
-            self.execute(instruction);
-            Some(())
-        } else {
-            None
+    /// `None` when it depends on the registers
+    fn static_successor(&self, pc: usize, instruction: Instruction) -> Option<i64> {
+        match instruction.flow() {
+            Flow::Next => Some(pc as i64 + 1),
+            Flow::Jump(Operand::Value(offset)) => Some((pc as i64).wrapping_add(offset)),
+            Flow::Jump(Operand::Register(_)) | Flow::Branch(_) => None,
+            Flow::Halt => Some(self.instructions.len() as i64),
         }
     }
 
-    pub fn fix(&mut self) {
-        let mut i = 0;
-        loop {
-            self.swap_jmp_nop(i);
-            if self.loops() {
-                self.swap_jmp_nop(i);
-                self.reset();
-                i += 1;
-            } else {
-                break;
+    /// Indexed up to and including `len`, the end of the program. Only valid when
+    /// [`Program::has_static_control_flow`].
+    fn halting_instructions(&self) -> Vec<bool> {
+        let len = self.instructions.len();
+        let mut predecessors = vec![Vec::new(); len + 1];
+        for (pc, &instruction) in self.instructions.iter().enumerate() {
+            if let Some(next) = self.static_successor(pc, instruction) {
+                if (0..=len as i64).contains(&next) {
+                    predecessors[next as usize].push(pc);
+                }
+            }
+        }
+        let mut halts = vec![false; len + 1];
+        halts[len] = true;
+        let mut stack = vec![len];
+        while let Some(pc) = stack.pop() {
+            for &predecessor in &predecessors[pc] {
+                if !mem::replace(&mut halts[predecessor], true) {
+                    stack.push(predecessor);
+                }
             }
         }
+        halts
+    }
+
+    /// Swaps the first `jmp` or `nop` on the program's path that would then land somewhere known
+    /// to halt, returning its index and the final accumulator.
+    pub fn fix(&mut self) -> Option<(usize, i64)> {
+        if !self.has_static_control_flow() {
+            return None;
+        }
+        let halts = self.halting_instructions();
+        if halts[0] {
+            return None;
+        }
+        let mut visited = vec![false; self.instructions.len()];
+        let mut pc = 0;
+        while let Some(i) = usize::try_from(pc)
+            .ok()
+            .filter(|&i| i < self.instructions.len() && !visited[i])
+        {
+            visited[i] = true;
+            let instruction = self.instructions[i];
+            let swapped_target = swap_jmp_nop(instruction)
+                .and_then(|swapped| self.static_successor(i, swapped))
+                .and_then(|target| usize::try_from(target).ok());
+            if let Some(target) = swapped_target.filter(|&target| target < halts.len()) {
+                if halts[target] {
+                    self.instructions[i] = swap_jmp_nop(instruction).unwrap();
+                    self.reset();
+                    self.run();
+                    return Some((i, self.get_acc()));
+                }
+            }
+            pc = self.static_successor(i, instruction)?;
+        }
+        None
     }
 
     fn reset(&mut self) {
-        self.accumulator = 0;
+        self.registers = [0; REGISTERS];
         self.program_counter = 0;
+        self.halted = false;
+        self.output.clear();
     }
+}
 
-    fn swap_jmp_nop(&mut self, i: usize) {
-        match self.instructions[i].operation {
-            Operation::Acc => {}
-            Operation::Jmp => self.instructions[i].operation = Operation::Nop,
-            Operation::Nop => self.instructions[i].operation = Operation::Jmp,
+impl fmt::Display for Program {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "acc: {}, pc: {}", self.get_acc(), self.program_counter)
+    }
+}
+
+/// A breakpoint stops before the instruction at `pc` runs.
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub enum DebugEvent {
+    Stepped,
+    Breakpoint { pc: usize },
+    AccChanged { pc: usize, from: i64, to: i64 },
+    Finished(RunOutcome),
+}
+
+impl fmt::Display for DebugEvent {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            DebugEvent::Stepped => write!(f, "stepped"),
+            DebugEvent::Breakpoint { pc } => write!(f, "breakpoint at {}", pc),
+            DebugEvent::AccChanged { pc, from, to } => {
+                write!(f, "acc changed from {} to {} at {}", from, to, pc)
+            }
+            DebugEvent::Finished(outcome) => write!(f, "finished: {:?}", outcome),
         }
     }
+}
 
-    pub fn loops(&mut self) -> bool {
-        let mut executed = vec![];
-        loop {
-            self.step();
-            let pc = self.program_counter;
-            if executed.contains(&pc) {
-                return true;
-            } else if self.program_counter == self.instructions.len() {
-                return false;
-            } else {
-                executed.push(pc)
+/// Runs a [`Program`] a bit at a time, keeping a trace of `(pc, instruction, acc after)`.
+pub struct Debugger {
+    program: Program,
+    breakpoints: HashSet<usize>,
+    watch_acc: bool,
+    trace: Vec<(usize, Instruction, i64)>,
+    loop_detector: LoopDetector,
+    finished: Option<RunOutcome>,
+}
+
+impl Debugger {
+    pub fn new(program: Program) -> Debugger {
+        Debugger {
+            loop_detector: LoopDetector::new(&program),
+            program,
+            breakpoints: HashSet::new(),
+            watch_acc: false,
+            trace: Vec::new(),
+            finished: None,
+        }
+    }
+
+    pub fn get_program(&self) -> &Program {
+        &self.program
+    }
+
+    pub fn get_trace(&self) -> &[(usize, Instruction, i64)] {
+        &self.trace
+    }
+
+    pub fn add_breakpoint(&mut self, pc: usize) {
+        self.breakpoints.insert(pc);
+    }
+
+    pub fn remove_breakpoint(&mut self, pc: usize) {
+        self.breakpoints.remove(&pc);
+    }
+
+    pub fn watch_acc(&mut self, watch: bool) {
+        self.watch_acc = watch;
+    }
+
+    /// once the program has finished, keeps reporting how without running anything
+    pub fn step(&mut self) -> DebugEvent {
+        if let Some(outcome) = self.finished {
+            return DebugEvent::Finished(outcome);
+        }
+        match self.program.fetch() {
+            Ok(pc) if self.loop_detector.repeated(pc, self.program.registers) => {
+                return self.finish(RunOutcome::InfiniteLoop {
+                    pc,
+                    acc: self.program.get_acc(),
+                });
             }
+            Ok(_) => {}
+            Err(outcome) => return self.finish(outcome),
         }
+        let from = self.program.get_acc();
+        match self.program.step() {
+            Ok((pc, instruction)) => {
+                let to = self.program.get_acc();
+                self.trace.push((pc, instruction, to));
+                if self.watch_acc && from != to {
+                    DebugEvent::AccChanged { pc, from, to }
+                } else {
+                    DebugEvent::Stepped
+                }
+            }
+            Err(outcome) => self.finish(outcome),
+        }
+    }
+
+    fn finish(&mut self, outcome: RunOutcome) -> DebugEvent {
+        self.finished = Some(outcome);
+        DebugEvent::Finished(outcome)
     }
 
-    pub fn run_until_loop(&mut self) {
-        let mut executed = vec![];
+    /// always runs at least one instruction so resuming from a breakpoint moves past it
+    pub fn resume(&mut self) -> DebugEvent {
+        let mut first = true;
         loop {
-            self.step();
-            let pc = self.program_counter;
-            if executed.contains(&pc) {
-                break;
-            } else {
-                executed.push(pc)
+            match self.program.fetch() {
+                Ok(pc) if !first && self.finished.is_none() && self.breakpoints.contains(&pc) => {
+                    return DebugEvent::Breakpoint { pc };
+                }
+                _ => {}
+            }
+            first = false;
+            match self.step() {
+                DebugEvent::Stepped => {}
+                event => return event,
             }
         }
     }
-}
-impl fmt::Display for Program {
-    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
-        write!(f, "acc: {}, pc: {}", self.accumulator, self.program_counter)
+
+    /// Commands are `step`, `continue`, `break <pc>`, `delete <pc>`, `watch`, `trace`, `print`
+    /// and `quit`, each of which can be shortened to its first letter.
+    pub fn repl<R: BufRead, W: Write>(&mut self, input: R, mut output: W) -> io::Result<()> {
+        for line in input.lines() {
+            let line = line?;
+            let mut words = line.split_whitespace();
+            let command = match words.next() {
+                Some(command) => command,
+                None => continue,
+            };
+            let argument = words.next().and_then(|pc| pc.parse::<usize>().ok());
+            match (command, argument) {
+                ("s", _) | ("step", _) => writeln!(output, "{}", self.step())?,
+                ("c", _) | ("continue", _) => writeln!(output, "{}", self.resume())?,
+                ("b", Some(pc)) | ("break", Some(pc)) => {
+                    self.add_breakpoint(pc);
+                    writeln!(output, "breakpoint set at {}", pc)?
+                }
+                ("d", Some(pc)) | ("delete", Some(pc)) => {
+                    self.remove_breakpoint(pc);
+                    writeln!(output, "breakpoint removed at {}", pc)?
+                }
+                ("w", _) | ("watch", _) => {
+                    self.watch_acc(!self.watch_acc);
+                    writeln!(output, "watching acc: {}", self.watch_acc)?
+                }
+                ("t", _) | ("trace", _) => {
+                    for (pc, instruction, acc) in &self.trace {
+                        writeln!(output, "{}: {} (acc: {})", pc, instruction, acc)?
+                    }
+                }
+                ("p", _) | ("print", _) => writeln!(output, "{}", self.program)?,
+                ("q", _) | ("quit", _) => break,
+                _ => writeln!(output, "unknown command: {}", line)?,
+            }
+        }
+        Ok(())
     }
 }
 
 #[cfg(test)]
 mod test {
-    use crate::day08::{Instruction, Program};
+    use crate::day08::{
+        assemble, disassemble, AssembleError, BasicBlock, ControlFlowGraph, DebugEvent, Debugger,
+        Instruction, Program, Register, RunOutcome, Successor, DEFAULT_STEP_LIMIT,
+    };
     use crate::loader::file_to_vec;
+    use std::fs::read_to_string;
 
     #[test]
     fn test_parse() {
@@ -196,33 +948,33 @@ mod test {
     #[test]
     fn test_program_small() {
         let mut prgm = Program::new(file_to_vec::<Instruction>("inputs/day08small.txt"));
-        prgm.run_until_loop();
+        assert_eq!(prgm.run(), RunOutcome::InfiniteLoop { pc: 1, acc: 5 });
         assert_eq!(5, prgm.get_acc());
     }
 
     #[test]
     fn test_program() {
         let mut prgm = Program::new(file_to_vec::<Instruction>("inputs/day08.txt"));
-        prgm.run_until_loop();
+        prgm.run();
         assert_eq!(1475, prgm.get_acc());
     }
 
     #[test]
     fn test_loops_true() {
         let mut prgm = Program::new(file_to_vec::<Instruction>("inputs/day08small.txt"));
-        assert!(prgm.loops());
+        assert!(matches!(prgm.run(), RunOutcome::InfiniteLoop { .. }));
     }
 
     #[test]
     fn test_loops_false() {
         let mut prgm = Program::new(file_to_vec::<Instruction>("inputs/day08small_no_loop.txt"));
-        assert!(!prgm.loops());
+        assert_eq!(prgm.run(), RunOutcome::Halted);
     }
 
     #[test]
     fn test_fix_small() {
         let mut prgm = Program::new(file_to_vec::<Instruction>("inputs/day08small.txt"));
-        prgm.fix();
+        assert_eq!(prgm.fix(), Some((7, 8)));
         assert_eq!(8, prgm.get_acc())
     }
 
@@ -230,6 +982,360 @@ mod test {
     fn test_fix() {
         let mut prgm = Program::new(file_to_vec::<Instruction>("inputs/day08.txt"));
         prgm.fix();
-        assert_eq!(1270, prgm.get_acc())
+        assert_eq!(1270, prgm.get_acc());
+        assert_eq!(prgm.run(), RunOutcome::Halted)
+    }
+
+    fn parse_program(source: &str) -> Program {
+        Program::new(source.lines().map(|line| line.parse().unwrap()).collect())
+    }
+
+    #[test]
+    fn test_instruction_round_trip() {
+        for line in &["acc +3", "jmp -4", "nop +0", "mul b a", "jnz c -2", "hlt"] {
+            assert_eq!(line.parse::<Instruction>().unwrap().to_string(), *line);
+        }
+        assert!("mul 3 a".parse::<Instruction>().is_err());
+        assert!("hlt +1".parse::<Instruction>().is_err());
+        assert!("foo +1".parse::<Instruction>().is_err());
+    }
+
+    #[test]
+    fn test_registers() {
+        // b = 5!, counting c down from 5
+        let mut prgm = parse_program(
+            "set b +1
+set c +5
+mul b c
+add c -1
+out b
+jnz c -3
+hlt
+acc +1000",
+        );
+        assert_eq!(prgm.run(), RunOutcome::Halted);
+        assert!(prgm.is_halted());
+        assert_eq!(prgm.get_register("b".parse::<Register>().unwrap()), 120);
+        assert_eq!(prgm.get_output(), &[5, 20, 60, 120, 120]);
+        assert_eq!(prgm.get_acc(), 0);
+    }
+
+    #[test]
+    fn test_jz() {
+        let mut prgm = parse_program(
+            "jz b +2
+acc +100
+acc +1",
+        );
+        prgm.run();
+        assert_eq!(prgm.get_acc(), 1);
+    }
+
+    #[test]
+    fn test_out_of_bounds() {
+        let mut prgm = parse_program("acc +1\njmp -2");
+        assert_eq!(prgm.run(), RunOutcome::OutOfBounds { pc: -1 });
+        let mut prgm = parse_program("jmp +3\nnop +0");
+        assert_eq!(prgm.run(), RunOutcome::OutOfBounds { pc: 3 });
+    }
+
+    #[test]
+    fn test_overflow_wraps() {
+        // b squares its way past 2^64 and wraps round to 0
+        let mut prgm = parse_program("set b +2\nmul b b\njnz b -1").with_step_limit(1000);
+        assert_eq!(prgm.run(), RunOutcome::Halted);
+        assert_eq!(prgm.get_register("b".parse().unwrap()), 0);
+        let mut prgm = parse_program("set a +9223372036854775807\nadd a +1");
+        assert_eq!(prgm.run(), RunOutcome::Halted);
+        assert_eq!(prgm.get_acc(), i64::MIN);
+        let source = "nop +0\njmp +9223372036854775807";
+        let mut prgm = parse_program(source);
+        assert_eq!(prgm.run(), RunOutcome::OutOfBounds { pc: i64::MIN });
+        assert!(disassemble(&assemble(source).unwrap()).contains("-> -9223372036854775808"));
+        assert!(!ControlFlowGraph::new(&assemble(source).unwrap()).can_terminate());
+    }
+
+    #[test]
+    fn test_register_loops() {
+        // counts b down from 3, only looping once the registers repeat
+        let mut prgm = parse_program("set b +3\nadd b -1\njnz b -1\nset b +1\njmp -3");
+        assert_eq!(prgm.run(), RunOutcome::InfiniteLoop { pc: 1, acc: 0 });
+        let mut prgm = parse_program("add b +1\njnz b -1").with_step_limit(100);
+        assert_eq!(prgm.run(), RunOutcome::StepLimit);
+        assert_eq!(prgm.get_register("b".parse().unwrap()), 50);
+        let mut prgm = parse_program("add b +1\njnz b -1");
+        assert_eq!(prgm.run(), RunOutcome::StepLimit);
+        assert_eq!(
+            prgm.get_register("b".parse().unwrap()),
+            DEFAULT_STEP_LIMIT as i64 / 2
+        );
+    }
+
+    #[test]
+    fn test_debugger() {
+        let mut debugger = Debugger::new(Program::new(file_to_vec::<Instruction>(
+            "inputs/day08small.txt",
+        )));
+        debugger.add_breakpoint(3);
+        assert_eq!(debugger.step(), DebugEvent::Stepped);
+        assert_eq!(debugger.resume(), DebugEvent::Breakpoint { pc: 3 });
+        assert_eq!(debugger.get_program().get_acc(), 2);
+        debugger.watch_acc(true);
+        assert_eq!(
+            debugger.resume(),
+            DebugEvent::AccChanged {
+                pc: 3,
+                from: 2,
+                to: 5
+            }
+        );
+        debugger.watch_acc(false);
+        assert_eq!(
+            debugger.resume(),
+            DebugEvent::Finished(RunOutcome::InfiniteLoop { pc: 1, acc: 5 })
+        );
+        assert_eq!(
+            debugger
+                .get_trace()
+                .iter()
+                .map(|(pc, _, _)| *pc)
+                .collect::<Vec<_>>(),
+            vec![0, 1, 2, 6, 7, 3, 4]
+        );
+    }
+
+    #[test]
+    fn test_debugger_breakpoint_on_loop() {
+        let mut debugger = Debugger::new(Program::new(file_to_vec::<Instruction>(
+            "inputs/day08small.txt",
+        )));
+        debugger.add_breakpoint(1);
+        assert_eq!(debugger.resume(), DebugEvent::Breakpoint { pc: 1 });
+        assert_eq!(debugger.resume(), DebugEvent::Breakpoint { pc: 1 });
+        let finished = DebugEvent::Finished(RunOutcome::InfiniteLoop { pc: 1, acc: 5 });
+        assert_eq!(debugger.resume(), finished);
+        assert_eq!(debugger.resume(), finished);
+        assert_eq!(debugger.step(), finished);
+        assert_eq!(debugger.get_trace().len(), 7);
+    }
+
+    #[test]
+    fn test_debugger_repl() {
+        let mut debugger = Debugger::new(parse_program("acc +1\njmp +2\nacc +5\nacc -3"));
+        let mut output = Vec::new();
+        debugger
+            .repl("b 3\nc\np\ns\ns\nt\nq\ns".as_bytes(), &mut output)
+            .unwrap();
+        assert_eq!(
+            String::from_utf8(output).unwrap(),
+            "breakpoint set at 3
+breakpoint at 3
+acc: 1, pc: 3
+stepped
+finished: Halted
+0: acc +1 (acc: 1)
+1: jmp +2 (acc: 1)
+3: acc -3 (acc: -2)
+"
+        );
+    }
+
+    #[test]
+    fn test_fix_unfixable() {
+        let mut prgm = parse_program("jmp +1\njmp -1\njmp -1");
+        assert_eq!(prgm.fix(), None);
+        let mut prgm = parse_program("set b +1\njnz b +0");
+        assert_eq!(prgm.fix(), None);
+    }
+
+    #[test]
+    fn test_fix_already_halts() {
+        let mut prgm = parse_program("nop +1\nacc +1");
+        assert_eq!(prgm.fix(), None);
+        assert_eq!(
+            prgm.instructions,
+            parse_program("nop +1\nacc +1").instructions
+        );
+        assert_eq!(parse_program("").fix(), None);
+    }
+
+    #[test]
+    fn test_assemble() {
+        let instructions = assemble(
+            "; b = 5!
+start = +5
+        set b +1
+        set c start
+loop:   mul b c ; b *= c
+        add c -1
+        jnz c loop
+end:    jmp end",
+        )
+        .unwrap();
+        assert_eq!(
+            instructions,
+            assemble("set b +1\nset c +5\nmul b c\nadd c -1\njnz c -2\njmp +0").unwrap()
+        );
+        let mut prgm = Program::new(instructions);
+        assert_eq!(prgm.run(), RunOutcome::InfiniteLoop { pc: 5, acc: 0 });
+        assert_eq!(prgm.get_register("b".parse().unwrap()), 120);
+    }
+
+    #[test]
+    fn test_assemble_errors() {
+        assert_eq!(
+            assemble("jmp nowhere"),
+            Err(AssembleError::InvalidInstruction {
+                line: 1,
+                text: String::from("jmp nowhere")
+            })
+        );
+        assert_eq!(
+            assemble("xy: nop +0\nxy = 3"),
+            Err(AssembleError::DuplicateName {
+                line: 2,
+                name: String::from("xy")
+            })
+        );
+        assert_eq!(
+            assemble("abc = +1\n\nb: nop +0"),
+            Err(AssembleError::ReservedName {
+                line: 3,
+                name: String::from("b")
+            })
+        );
+        assert_eq!(
+            assemble("jmp: nop +0\njmp jmp"),
+            Err(AssembleError::ReservedName {
+                line: 1,
+                name: String::from("jmp")
+            })
+        );
+        assert_eq!(
+            assemble("nop +0\nacc = 2"),
+            Err(AssembleError::ReservedName {
+                line: 2,
+                name: String::from("acc")
+            })
+        );
+        assert_eq!(
+            assemble("nop +0\n: nop +0"),
+            Err(AssembleError::EmptyName { line: 2 })
+        );
+        assert_eq!(assemble(" = 4"), Err(AssembleError::EmptyName { line: 1 }));
+        assert_eq!(
+            assemble("5 = 7\nacc 5"),
+            Err(AssembleError::InvalidName {
+                line: 1,
+                name: String::from("5")
+            })
+        );
+        assert_eq!(
+            assemble("nop +0\nmy loop: nop +0\njmp my"),
+            Err(AssembleError::InvalidName {
+                line: 2,
+                name: String::from("my loop")
+            })
+        );
+    }
+
+    #[test]
+    fn test_disassemble() {
+        let instructions = file_to_vec::<Instruction>("inputs/day08small.txt");
+        let disassembly = disassemble(&instructions);
+        assert_eq!(
+            disassembly.lines().take(3).collect::<Vec<_>>(),
+            vec![
+                "nop +0          ; 0",
+                "acc +1          ; 1",
+                "jmp +4          ; 2 -> 6"
+            ]
+        );
+        assert_eq!(assemble(&disassembly).unwrap(), instructions);
+
+        let source = read_to_string("inputs/day08.txt").unwrap();
+        assert_eq!(
+            assemble(&disassemble(&assemble(&source).unwrap())),
+            assemble(&source)
+        );
+    }
+
+    #[test]
+    fn test_control_flow_graph_small() {
+        let instructions = file_to_vec::<Instruction>("inputs/day08small.txt");
+        let cfg = ControlFlowGraph::new(&instructions);
+        assert_eq!(
+            cfg.blocks(),
+            &[
+                BasicBlock {
+                    start: 0,
+                    end: 1,
+                    successors: vec![Successor::Instruction(1)]
+                },
+                BasicBlock {
+                    start: 1,
+                    end: 3,
+                    successors: vec![Successor::Instruction(6)]
+                },
+                BasicBlock {
+                    start: 3,
+                    end: 5,
+                    successors: vec![Successor::Instruction(1)]
+                },
+                BasicBlock {
+                    start: 5,
+                    end: 6,
+                    successors: vec![Successor::Instruction(6)]
+                },
+                BasicBlock {
+                    start: 6,
+                    end: 8,
+                    successors: vec![Successor::Instruction(3)]
+                },
+                BasicBlock {
+                    start: 8,
+                    end: 9,
+                    successors: vec![Successor::Exit]
+                },
+            ]
+        );
+        assert_eq!(cfg.unreachable_instructions(), vec![5, 8]);
+        assert_eq!(cfg.instructions_on_cycles(), vec![1, 2, 3, 4, 6, 7]);
+        assert!(!cfg.can_terminate());
+
+        let cfg =
+            ControlFlowGraph::new(&file_to_vec::<Instruction>("inputs/day08small_no_loop.txt"));
+        assert!(cfg.can_terminate());
+        assert!(cfg.instructions_on_cycles().is_empty());
+    }
+
+    #[test]
+    fn test_control_flow_graph_agrees_with_run() {
+        let mut prgm = Program::new(file_to_vec::<Instruction>("inputs/day08.txt"));
+        assert!(!ControlFlowGraph::new(&prgm.instructions).can_terminate());
+        assert!(matches!(prgm.run(), RunOutcome::InfiniteLoop { .. }));
+        prgm.fix();
+        assert!(ControlFlowGraph::new(&prgm.instructions).can_terminate());
+    }
+
+    #[test]
+    fn test_control_flow_graph_register_jump() {
+        let instructions = assemble("set b +2\njmp b\nacc +1\nhlt").unwrap();
+        let graph = ControlFlowGraph::new(&instructions);
+        assert_eq!(graph.unreachable_instructions(), Vec::<usize>::new());
+        assert!(graph.can_terminate());
+        // the register jump can't be reached, so it doesn't count
+        let instructions = assemble("jmp +2\njmp b\nhlt\nacc +1").unwrap();
+        let graph = ControlFlowGraph::new(&instructions);
+        assert_eq!(graph.unreachable_instructions(), vec![1, 3]);
+    }
+
+    #[test]
+    fn test_control_flow_graph_conditional() {
+        let cfg = ControlFlowGraph::new(&assemble("loop: jnz b loop\nhlt\nacc +1").unwrap());
+        assert!(cfg.can_terminate());
+        assert_eq!(cfg.instructions_on_cycles(), vec![0]);
+        assert_eq!(cfg.unreachable_instructions(), vec![2]);
+        assert!(ControlFlowGraph::new(&[]).can_terminate());
     }
 }
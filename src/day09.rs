@@ -1,73 +1,223 @@
-use crate::day01::expense_report::ComboSums;
-use std::cmp::Ordering;
+use std::collections::{HashMap, VecDeque};
+use std::fmt;
+use std::hash::Hash;
+use std::io::{self, BufRead};
+use std::iter;
+use std::marker::PhantomData;
+use std::ops::{Add, Range};
 
-pub struct XMAS {
+pub trait XMASValue: Copy + Ord + Hash + fmt::Debug + Add<Output = Self> {
+    fn checked_add(self, rhs: Self) -> Option<Self>;
+    /// wide enough that summing any `Vec` of these can't overflow
+    fn widen(self) -> i128;
+}
+
+macro_rules! impl_xmas_value {
+    ($($t:ty),*) => {
+        $(impl XMASValue for $t {
+            fn checked_add(self, rhs: Self) -> Option<Self> {
+                <$t>::checked_add(self, rhs)
+            }
+
+            fn widen(self) -> i128 {
+                i128::from(self)
+            }
+        })*
+    };
+}
+
+impl_xmas_value!(i32, i64, u64);
+
+#[derive(Debug, PartialEq)]
+pub enum XMASError {
+    NoRuleBreaker,
+}
+
+impl fmt::Display for XMASError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            XMASError::NoRuleBreaker => write!(f, "every number follows the rule"),
+        }
+    }
+}
+
+pub struct XMASBuilder<T> {
     preamble_len: usize,
-    contents: Vec<i32>,
+    value: PhantomData<T>,
 }
 
-impl XMAS {
-    fn new(contents: Vec<i32>) -> XMAS {
+impl<T: XMASValue> XMASBuilder<T> {
+    pub fn preamble_len(mut self, preamble_len: usize) -> XMASBuilder<T> {
+        self.preamble_len = preamble_len;
+        self
+    }
+
+    pub fn build(self, contents: Vec<T>) -> XMAS<T> {
         XMAS {
             contents,
+            preamble_len: self.preamble_len,
+        }
+    }
+}
+
+pub struct XMAS<T> {
+    preamble_len: usize,
+    contents: Vec<T>,
+}
+
+impl<T: XMASValue> XMAS<T> {
+    /// the preamble defaults to the puzzle's 25
+    pub fn builder() -> XMASBuilder<T> {
+        XMASBuilder {
             preamble_len: 25,
+            value: PhantomData,
         }
     }
 
-    fn find_contiguous_summing_to(&self, goal: i32) -> i32 {
-        let mut contiguous = Vec::new();
-        let mut i = 0;
-        let mut sum = 0;
-        loop {
-            match sum.cmp(&goal) {
-                Ordering::Less => {
-                    let new_value = self.contents[i];
-                    contiguous.push(new_value);
-                    sum += new_value;
-                    i += 1
-                }
-                Ordering::Equal => {
-                    return contiguous.iter().max().unwrap() + contiguous.iter().min().unwrap();
-                }
-                Ordering::Greater => {
-                    sum -= contiguous.remove(0);
-                }
+    fn new(contents: Vec<T>) -> XMAS<T> {
+        XMAS::builder().build(contents)
+    }
+
+    /// ranges hold at least two numbers
+    pub fn find_contiguous_summing_to(&self, goal: T) -> Option<Range<usize>> {
+        // prefix_sums[i] is the sum of the first i numbers
+        let mut prefix_sums = vec![0];
+        let mut first_index_of = HashMap::new();
+        for (i, &value) in self.contents.iter().enumerate() {
+            let end = i + 1;
+            prefix_sums.push(prefix_sums[i] + value.widen());
+            if end < 2 {
+                continue;
+            }
+            first_index_of
+                .entry(prefix_sums[end - 2])
+                .or_insert(end - 2);
+            if let Some(&start) = first_index_of.get(&(prefix_sums[end] - goal.widen())) {
+                return Some(start..end);
             }
         }
+        None
+    }
+
+    pub fn encryption_weakness(&self, goal: T) -> Option<T> {
+        let range = &self.contents[self.find_contiguous_summing_to(goal)?];
+        Some(*range.iter().min()? + *range.iter().max()?)
+    }
+
+    fn find_rule_breaker(&self) -> Result<T, XMASError> {
+        self.rule_breakers()
+            .next()
+            .map(|(_, value)| value)
+            .ok_or(XMASError::NoRuleBreaker)
+    }
+
+    pub fn rule_breakers(&self) -> impl Iterator<Item = (usize, T)> + '_ {
+        XMASValidator::new(self.preamble_len).rule_breakers(self.contents.iter().copied())
+    }
+}
+
+/// Keeps a count of every pair sum in the window so each new number costs one pass over it.
+pub struct XMASValidator<T> {
+    preamble_len: usize,
+    position: usize,
+    window: VecDeque<T>,
+    pair_sums: HashMap<T, usize>,
+}
+
+impl<T: XMASValue> XMASValidator<T> {
+    pub fn new(preamble_len: usize) -> XMASValidator<T> {
+        XMASValidator {
+            preamble_len,
+            position: 0,
+            window: VecDeque::with_capacity(preamble_len + 1),
+            pair_sums: HashMap::new(),
+        }
     }
 
-    fn find_rule_breaker(&self) -> i32 {
-        for i in self.preamble_len..self.contents.len() {
-            if !self.is_pair_summing_to(i as usize) {
-                return *self.contents.get(i).unwrap() as i32;
+    /// numbers in the preamble always follow the rule
+    pub fn push(&mut self, value: T) -> bool {
+        let valid = self.window.len() < self.preamble_len || self.pair_sums.contains_key(&value);
+        for &other in &self.window {
+            if let Some(sum) = value.checked_add(other) {
+                *self.pair_sums.entry(sum).or_insert(0) += 1;
+            }
+        }
+        self.window.push_back(value);
+        if self.window.len() > self.preamble_len {
+            let oldest = self.window.pop_front().unwrap();
+            for &other in &self.window {
+                if let Some(sum) = oldest.checked_add(other) {
+                    let count = self.pair_sums.get_mut(&sum).unwrap();
+                    *count -= 1;
+                    if *count == 0 {
+                        self.pair_sums.remove(&sum);
+                    }
+                }
             }
         }
-        unreachable!("should be a number here, I expect valid input")
+        self.position += 1;
+        valid
     }
 
-    fn is_pair_summing_to(&self, index: usize) -> bool {
-        self.contents
-            .split_at(index)
-            .0
-            .iter()
-            .rev()
-            .take(self.preamble_len)
-            .copied()
-            .collect::<Vec<i32>>()
-            .find_combo(self.contents[index], 2)
-            .is_some()
+    pub fn rule_breakers<I: IntoIterator<Item = T>>(
+        mut self,
+        values: I,
+    ) -> impl Iterator<Item = (usize, T)> {
+        values.into_iter().filter_map(move |value| {
+            let position = self.position;
+            if self.push(value) {
+                None
+            } else {
+                Some((position, value))
+            }
+        })
+    }
+
+    /// Positions are line indices. Stops after the first read error or line that isn't a
+    /// number, which is yielded as an error.
+    pub fn rule_breakers_in<R: BufRead>(
+        mut self,
+        reader: R,
+    ) -> impl Iterator<Item = io::Result<(usize, T)>>
+    where
+        T: std::str::FromStr,
+    {
+        let mut lines = reader.lines();
+        let mut failed = false;
+        iter::from_fn(move || {
+            while !failed {
+                let position = self.position;
+                let value = lines.next()?.and_then(|line| {
+                    line.trim().parse().map_err(|_| {
+                        io::Error::new(
+                            io::ErrorKind::InvalidData,
+                            format!("line {}: \"{}\" is not a number", position + 1, line),
+                        )
+                    })
+                });
+                match value {
+                    Ok(value) if self.push(value) => {}
+                    Ok(value) => return Some(Ok((position, value))),
+                    Err(error) => {
+                        failed = true;
+                        return Some(Err(error));
+                    }
+                }
+            }
+            None
+        })
     }
 }
 
-impl std::convert::From<Vec<i32>> for XMAS {
-    fn from(v: Vec<i32>) -> Self {
+impl<T: XMASValue> std::convert::From<Vec<T>> for XMAS<T> {
+    fn from(v: Vec<T>) -> Self {
         XMAS::new(v)
     }
 }
 
 #[cfg(test)]
 mod test {
-    use crate::day09::XMAS;
+    use crate::day09::{XMASError, XMASValidator, XMAS};
     use crate::loader::file_to_vec;
 
     #[test]
@@ -81,23 +231,112 @@ mod test {
     }
 
     #[test]
-    #[ignore] // changed preamble len for large input
     fn test_find_anomaly_small() {
-        let res = XMAS::from(file_to_vec::<i32>("inputs/day09small.txt")).find_rule_breaker();
-        assert_eq!(res, 127)
+        let res = XMAS::<i32>::builder()
+            .preamble_len(5)
+            .build(file_to_vec::<i32>("inputs/day09small.txt"))
+            .find_rule_breaker();
+        assert_eq!(res, Ok(127))
     }
 
     #[test]
     fn test_find_anomaly_large() {
         let res = XMAS::from(file_to_vec::<i32>("inputs/day09.txt")).find_rule_breaker();
-        assert_eq!(res, 36845998)
+        assert_eq!(res, Ok(36845998))
     }
 
     #[test]
     fn test_find_vec_summing_to_anomaly_large() {
         let code = XMAS::from(file_to_vec::<i32>("inputs/day09.txt"));
-        let rule_breaker = code.find_rule_breaker();
-        let res = code.find_contiguous_summing_to(rule_breaker);
-        assert_eq!(4830226, res);
+        let rule_breaker = code.find_rule_breaker().unwrap();
+        let res = code.encryption_weakness(rule_breaker);
+        assert_eq!(Some(4830226), res);
+    }
+
+    #[test]
+    fn test_no_rule_breaker() {
+        let code = XMAS::<u64>::builder()
+            .preamble_len(2)
+            .build(vec![1, 2, 3, 5, 8]);
+        assert_eq!(code.find_rule_breaker(), Err(XMASError::NoRuleBreaker));
+    }
+
+    #[test]
+    fn test_wide_values() {
+        let big: u64 = 1 << 62;
+        let code =
+            XMAS::<u64>::builder()
+                .preamble_len(2)
+                .build(vec![big, big + 1, 2 * big + 1, 3 * big]);
+        assert_eq!(code.find_rule_breaker(), Ok(3 * big));
+        let big: i64 = 1 << 62;
+        let code = XMAS::<i64>::builder()
+            .preamble_len(2)
+            .build(vec![-big, big, 0, big, -big]);
+        assert_eq!(code.find_rule_breaker(), Ok(-big));
+    }
+
+    #[test]
+    fn test_every_rule_breaker() {
+        let code = XMAS::<i64>::builder()
+            .preamble_len(5)
+            .build(file_to_vec::<i64>("inputs/day09small.txt"));
+        assert_eq!(code.rule_breakers().collect::<Vec<_>>(), vec![(14, 127)]);
+
+        let mut validator = XMASValidator::<i32>::new(2);
+        let pushed = [1, 2, 3, 4, 9, 13, 1]
+            .iter()
+            .map(|&value| validator.push(value))
+            .collect::<Vec<_>>();
+        assert_eq!(pushed, vec![true, true, true, false, false, true, false]);
+    }
+
+    #[test]
+    fn test_rule_breakers_in_reader() {
+        let input = "1\n1\n2\n3\n6\n";
+        let rule_breakers = XMASValidator::<u64>::new(2)
+            .rule_breakers_in(input.as_bytes())
+            .collect::<Result<Vec<_>, _>>()
+            .unwrap();
+        assert_eq!(rule_breakers, vec![(4, 6)]);
+
+        let input = "1\n1\n7\nnot a number\n6\n";
+        let mut rule_breakers = XMASValidator::<u64>::new(2).rule_breakers_in(input.as_bytes());
+        assert_eq!(rule_breakers.next().unwrap().unwrap(), (2, 7));
+        assert_eq!(
+            rule_breakers.next().unwrap().unwrap_err().to_string(),
+            "line 4: \"not a number\" is not a number"
+        );
+        assert!(rule_breakers.next().is_none());
+    }
+
+    #[test]
+    fn test_find_contiguous_small() {
+        let code = XMAS::from(file_to_vec::<i32>("inputs/day09small.txt"));
+        assert_eq!(code.find_contiguous_summing_to(127), Some(2..6));
+        assert_eq!(code.encryption_weakness(127), Some(62));
+    }
+
+    #[test]
+    fn test_find_contiguous_edge_cases() {
+        let code = XMAS::from(vec![5, -3, 10, 4, -1]);
+        // a lone -1 doesn't count
+        assert_eq!(code.find_contiguous_summing_to(-1), None);
+        assert_eq!(code.find_contiguous_summing_to(10), Some(1..5));
+        assert_eq!(code.find_contiguous_summing_to(7), Some(1..3));
+        assert_eq!(code.find_contiguous_summing_to(3), Some(3..5));
+        assert_eq!(code.find_contiguous_summing_to(100), None);
+        assert_eq!(
+            XMAS::<u64>::from(vec![]).find_contiguous_summing_to(0),
+            None
+        );
+        assert_eq!(
+            XMAS::<i32>::from(vec![i32::MAX, 1, 2]).find_contiguous_summing_to(3),
+            Some(1..3)
+        );
+        assert_eq!(
+            XMAS::<u64>::from(vec![u64::MAX, u64::MAX, 1]).find_contiguous_summing_to(2),
+            None
+        );
     }
 }
@@ -1,59 +1,177 @@
-use std::collections::HashMap;
+use std::collections::BTreeMap;
+use std::fmt;
 use std::ops::{Index, IndexMut};
 
-struct DaisyChain {
-    adaptors: Vec<usize>,
+#[derive(Debug, Clone)]
+pub struct JoltageRules {
+    allowed_gaps: Vec<usize>,
+    device_offset: usize,
 }
 
-impl DaisyChain {
-    fn contains(&self, index: &usize) -> bool {
-        self.adaptors.contains(index)
+impl JoltageRules {
+    pub fn new(allowed_gaps: Vec<usize>, device_offset: usize) -> JoltageRules {
+        JoltageRules {
+            allowed_gaps,
+            device_offset,
+        }
+    }
+
+    fn max_gap(&self) -> usize {
+        self.allowed_gaps.iter().copied().max().unwrap_or(0)
+    }
+
+    fn allows(&self, gap: usize) -> bool {
+        self.allowed_gaps.contains(&gap)
+    }
+}
+
+impl Default for JoltageRules {
+    fn default() -> Self {
+        JoltageRules::new(vec![1, 2, 3], 3)
+    }
+}
+
+#[derive(Debug, PartialEq)]
+pub enum ChainError {
+    GapTooLarge { from: usize, to: usize },
+    DisallowedGap { from: usize, to: usize },
+}
+
+impl fmt::Display for ChainError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ChainError::GapTooLarge { from, to } | ChainError::DisallowedGap { from, to } => {
+                write!(f, "can't get from {} jolts to {} jolts", from, to)
+            }
+        }
     }
 }
 
+pub struct DaisyChain {
+    adaptors: Vec<usize>,
+    rules: JoltageRules,
+}
+
 impl DaisyChain {
-    fn new(mut adaptors: Vec<usize>) -> DaisyChain {
+    pub fn new(adaptors: Vec<usize>) -> Result<DaisyChain, ChainError> {
+        DaisyChain::with_rules(adaptors, JoltageRules::default())
+    }
+
+    /// adds the outlet and device to the adaptors
+    pub fn with_rules(
+        mut adaptors: Vec<usize>,
+        rules: JoltageRules,
+    ) -> Result<DaisyChain, ChainError> {
         adaptors.insert(0, 0);
-        adaptors.push(adaptors.iter().max().unwrap() + 3);
+        adaptors.push(adaptors.iter().max().unwrap() + rules.device_offset);
         adaptors.sort_unstable();
-        DaisyChain { adaptors }
+        if let Some(pair) = adaptors
+            .windows(2)
+            .find(|pair| !rules.allows(pair[1] - pair[0]))
+        {
+            let (from, to) = (pair[0], pair[1]);
+            return Err(if to - from > rules.max_gap() {
+                ChainError::GapTooLarge { from, to }
+            } else {
+                ChainError::DisallowedGap { from, to }
+            });
+        }
+        Ok(DaisyChain { adaptors, rules })
+    }
+
+    pub fn gap_histogram(&self) -> BTreeMap<usize, usize> {
+        let mut histogram = BTreeMap::new();
+        for pair in self.adaptors.windows(2) {
+            *histogram.entry(pair[1] - pair[0]).or_insert(0) += 1;
+        }
+        histogram
     }
 
     pub fn part_one(&self) -> usize {
-        let mut count = (0, 0, 0);
-
-        let mut curr_adapter = 0;
-        for adaptor in self.adaptors.iter() {
-            let diff = adaptor - curr_adapter;
-            if diff == 1 {
-                count.0 += 1;
-            } else if diff == 3 {
-                count.2 += 1;
+        let histogram = self.gap_histogram();
+        histogram.get(&1).unwrap_or(&0) * histogram.get(&3).unwrap_or(&0)
+    }
+
+    /// `None` if the count doesn't fit in a `u128`
+    pub fn part_two(&self) -> Option<u128> {
+        let max_gap = self.rules.max_gap();
+        let mut ways = vec![0_u128; self.adaptors.len()];
+        ways[0] = 1;
+        for i in 1..self.adaptors.len() {
+            let mut total = 0_u128;
+            for j in (0..i).rev() {
+                let gap = self.adaptors[i] - self.adaptors[j];
+                if gap > max_gap {
+                    break;
+                }
+                if self.rules.allows(gap) {
+                    total = total.checked_add(ways[j])?;
+                }
             }
-            curr_adapter = *adaptor;
+            ways[i] = total;
         }
-
-        count.0 * count.2
+        ways.last().copied()
     }
 
-    pub fn part_two(&self) -> usize {
-        let &final_number = self.adaptors.last().unwrap();
-        let mut hits: HashMap<usize, usize> = HashMap::new();
+    /// Arrangements include the outlet and device. There can be very many, so this is meant for
+    /// small bags of adaptors.
+    pub fn arrangements(&self) -> Arrangements<'_> {
+        let max_gap = self.rules.max_gap();
+        let last = self.adaptors.len() - 1;
+        let mut reaches_device = vec![false; self.adaptors.len()];
+        reaches_device[last] = true;
+        for i in (0..last).rev() {
+            reaches_device[i] = self.adaptors[i + 1..]
+                .iter()
+                .zip(&reaches_device[i + 1..])
+                .take_while(|(&adaptor, _)| adaptor - self.adaptors[i] <= max_gap)
+                .any(|(&adaptor, &reaches)| {
+                    reaches && self.rules.allows(adaptor - self.adaptors[i])
+                })
+        }
+        Arrangements {
+            stack: if reaches_device[0] {
+                vec![(0, 1)]
+            } else {
+                Vec::new()
+            },
+            chain: self,
+            reaches_device,
+        }
+    }
+}
 
-        hits.insert(self[0], 1);
+pub struct Arrangements<'a> {
+    chain: &'a DaisyChain,
+    reaches_device: Vec<bool>,
+    /// the adaptors in the arrangement so far, each with the next adaptor to try after it
+    stack: Vec<(usize, usize)>,
+}
 
-        for i in self.adaptors.iter() {
-            let curr_hits = *hits.get(&i).unwrap();
+impl Iterator for Arrangements<'_> {
+    type Item = Vec<usize>;
 
-            for step in 1..4 {
-                let i_next = i + step;
-                if self.contains(&i_next) {
-                    *hits.entry(i_next).or_insert(0) += curr_hits;
+    fn next(&mut self) -> Option<Self::Item> {
+        let adaptors = &self.chain.adaptors;
+        let max_gap = self.chain.rules.max_gap();
+        loop {
+            let &(i, next) = self.stack.last()?;
+            if i == adaptors.len() - 1 {
+                let arrangement = self.stack.iter().map(|&(i, _)| adaptors[i]).collect();
+                self.stack.pop();
+                return Some(arrangement);
+            }
+            if next < adaptors.len() && adaptors[next] - adaptors[i] <= max_gap {
+                self.stack.last_mut().unwrap().1 += 1;
+                if self.reaches_device[next]
+                    && self.chain.rules.allows(adaptors[next] - adaptors[i])
+                {
+                    self.stack.push((next, next + 1));
                 }
+            } else {
+                self.stack.pop();
             }
         }
-
-        return *hits.get(&final_number).unwrap();
     }
 }
 
@@ -78,30 +196,87 @@ mod tests {
 
     #[test]
     fn test_parse() {
-        DaisyChain::new(file_to_vec("inputs/day10.txt"));
+        DaisyChain::new(file_to_vec("inputs/day10.txt")).unwrap();
     }
 
     #[test]
     fn test_small() {
-        let chain = DaisyChain::new(file_to_vec("inputs/day10small.txt"));
+        let chain = DaisyChain::new(file_to_vec("inputs/day10small.txt")).unwrap();
         assert_eq!(35, chain.part_one());
     }
 
     #[test]
     fn test_medium() {
-        let chain = DaisyChain::new(file_to_vec("inputs/day10medium.txt"));
+        let chain = DaisyChain::new(file_to_vec("inputs/day10medium.txt")).unwrap();
         assert_eq!(220, chain.part_one());
     }
 
     #[test]
     fn test_p1() {
-        let chain = DaisyChain::new(file_to_vec("inputs/day10.txt"));
+        let chain = DaisyChain::new(file_to_vec("inputs/day10.txt")).unwrap();
         assert_eq!(2482, chain.part_one());
     }
 
     #[test]
     fn test_p2() {
-        let chain = DaisyChain::new(file_to_vec("inputs/day10.txt"));
-        assert_eq!(96717311574016, chain.part_two());
+        let chain = DaisyChain::new(file_to_vec("inputs/day10.txt")).unwrap();
+        assert_eq!(Some(96717311574016), chain.part_two());
+    }
+
+    #[test]
+    fn test_gap_histogram() {
+        let chain = DaisyChain::new(file_to_vec("inputs/day10medium.txt")).unwrap();
+        assert_eq!(
+            chain.gap_histogram().into_iter().collect::<Vec<_>>(),
+            vec![(1, 22), (3, 10)]
+        );
+    }
+
+    #[test]
+    fn test_custom_rules() {
+        let chain =
+            DaisyChain::with_rules(vec![2, 4, 5, 9], JoltageRules::new(vec![1, 2, 4], 1)).unwrap();
+        assert_eq!(
+            chain.gap_histogram().into_iter().collect::<Vec<_>>(),
+            vec![(1, 2), (2, 2), (4, 1)]
+        );
+        // 0 2 4 5 9 10, or the same skipping 2
+        assert_eq!(chain.part_two(), Some(2));
+        assert_eq!(
+            DaisyChain::with_rules(vec![2, 4, 9], JoltageRules::new(vec![1, 2, 3], 3)).err(),
+            Some(ChainError::GapTooLarge { from: 4, to: 9 })
+        );
+        assert_eq!(
+            DaisyChain::with_rules(vec![2], JoltageRules::new(vec![1, 3], 3)).err(),
+            Some(ChainError::DisallowedGap { from: 0, to: 2 })
+        );
+        // the device is 2 jolts above the last adaptor, which isn't an allowed jump
+        assert_eq!(
+            DaisyChain::with_rules(vec![1], JoltageRules::new(vec![1, 3], 2)).err(),
+            Some(ChainError::DisallowedGap { from: 1, to: 3 })
+        );
+    }
+
+    #[test]
+    fn test_arrangements() {
+        let chain = DaisyChain::new(file_to_vec("inputs/day10small.txt")).unwrap();
+        let arrangements = chain.arrangements().collect::<Vec<_>>();
+        assert_eq!(arrangements.len() as u128, chain.part_two().unwrap());
+        assert_eq!(
+            arrangements[0],
+            vec![0, 1, 4, 5, 6, 7, 10, 11, 12, 15, 16, 19, 22]
+        );
+        assert_eq!(arrangements[7], vec![0, 1, 4, 7, 10, 12, 15, 16, 19, 22]);
+
+        let chain = DaisyChain::new(file_to_vec("inputs/day10medium.txt")).unwrap();
+        assert_eq!(chain.arrangements().count(), 19208);
+    }
+
+    #[test]
+    fn test_part_two_overflow() {
+        let chain = DaisyChain::new((1..100).collect()).unwrap();
+        assert!(chain.part_two().is_some());
+        let chain = DaisyChain::new((1..200).collect()).unwrap();
+        assert_eq!(chain.part_two(), None);
     }
 }
@@ -1,4 +1,9 @@
+use crate::automaton::{Adjacent, Automaton, Cell, Grid, LineOfSight, Rule, Settled};
 use core::fmt;
+use std::convert::TryFrom;
+use std::io::{self, Write};
+use std::thread;
+use std::time::Duration;
 
 #[derive(Eq, PartialEq, Copy, Clone)]
 enum Spot {
@@ -18,59 +23,38 @@ impl fmt::Debug for Spot {
 }
 
 impl Spot {
-    fn outcome(seats: [[Spot; 3]; 3]) -> Spot {
-        let focus = seats[1][1];
-        if focus == Spot::Floor {
-            Spot::Floor
-        } else if Spot::Empty == focus && Spot::empty_surroundings(&seats) {
-            Spot::Occupied
-        } else if Spot::Occupied == focus && Spot::has_four_or_more_surrounding(seats) {
-            Spot::Empty
-        } else {
-            focus
+    fn rgb(self) -> [u8; 3] {
+        match self {
+            Spot::Floor => [40, 40, 40],
+            Spot::Empty => [90, 160, 90],
+            Spot::Occupied => [210, 60, 60],
         }
     }
 
-    fn outcome_v2(seats: [[Spot; 3]; 3]) -> Spot {
-        let focus = seats[1][1];
-        if focus == Spot::Floor {
-            Spot::Floor
-        } else if Spot::Empty == focus && Spot::empty_surroundings(&seats) {
-            Spot::Occupied
-        } else if Spot::Occupied == focus && Spot::has_five_or_more_surrounding(seats) {
-            Spot::Empty
-        } else {
-            focus
+    fn ansi(self) -> &'static str {
+        match self {
+            Spot::Floor => "\x1b[90m.\x1b[0m",
+            Spot::Empty => "\x1b[32mL\x1b[0m",
+            Spot::Occupied => "\x1b[31m#\x1b[0m",
         }
     }
+}
 
-    fn has_five_or_more_surrounding(seats: [[Spot; 3]; 3]) -> bool {
-        seats
-            .iter()
-            .flatten()
-            .enumerate()
-            .filter(|(i, _)| i.ne(&4))
-            .filter(|(_, s)| Spot::Occupied.eq(s))
-            .count()
-            >= 5
+impl Cell for Spot {
+    fn is_fixed(self) -> bool {
+        self == Spot::Floor
     }
 
-    fn has_four_or_more_surrounding(seats: [[Spot; 3]; 3]) -> bool {
-        seats
-            .iter()
-            .flatten()
-            .enumerate()
-            .filter(|(i, _)| i.ne(&4))
-            .filter(|(_, s)| Spot::Occupied.eq(s))
-            .count()
-            >= 4
+    fn is_alive(self) -> bool {
+        self == Spot::Occupied
     }
 
-    fn empty_surroundings(seats: &[[Spot; 3]; 3]) -> bool {
-        seats
-            .iter()
-            .flatten()
-            .all(|s| matches!(s, Spot::Empty | Spot::Floor))
+    fn with_alive(self, alive: bool) -> Self {
+        if alive {
+            Spot::Occupied
+        } else {
+            Spot::Empty
+        }
     }
 }
 
@@ -85,147 +69,290 @@ impl From<char> for Spot {
     }
 }
 
+pub const MAX_GENERATIONS: usize = 1000;
+
+/// Part one looks at the eight adjacent seats and leaves once four are taken, part two at the
+/// first seat visible in each direction and leaves once five are taken.
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum SeatingRules {
+    Adjacent,
+    LineOfSight,
+    Custom { line_of_sight: bool, crowd: usize },
+}
+
+impl SeatingRules {
+    fn automaton(self, spots: Grid<Spot>) -> Automaton<Spot> {
+        let (line_of_sight, crowd) = match self {
+            SeatingRules::Adjacent => (false, 4),
+            SeatingRules::LineOfSight => (true, 5),
+            SeatingRules::Custom {
+                line_of_sight,
+                crowd,
+            } => (line_of_sight, crowd),
+        };
+        // a seat fills up when nobody around it is sitting
+        let rule = Rule::new(&[0], &(0..crowd).collect::<Vec<_>>());
+        if line_of_sight {
+            Automaton::new(spots, LineOfSight, rule)
+        } else {
+            Automaton::new(spots, Adjacent, rule)
+        }
+    }
+}
+
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum Outcome {
+    Stable { generation: usize, occupied: usize },
+    Cycle { start: usize, period: usize },
+    GenerationLimit,
+}
+
+impl Outcome {
+    fn new(settled: Settled, spots: &Grid<Spot>) -> Outcome {
+        match settled {
+            Settled::Stable { generation } => Outcome::Stable {
+                generation,
+                occupied: spots.alive_count(),
+            },
+            Settled::Cycle { start, period } => Outcome::Cycle { start, period },
+            Settled::GenerationLimit => Outcome::GenerationLimit,
+        }
+    }
+}
+
 #[derive(Eq, PartialEq, Clone)]
 pub struct WaitingArea {
-    spots: Vec<Vec<Spot>>,
+    spots: Grid<Spot>,
 }
 
 impl fmt::Display for WaitingArea {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
-        let mut out = String::new();
-        for row in &self.spots {
-            out.push_str(format!("{:?} \n", row).replace(", ", "").as_str());
+        for row in self.spots.rows() {
+            for spot in row {
+                write!(f, "{:?}", spot)?;
+            }
+            writeln!(f)?;
         }
-        write!(f, "{}", out.as_str().replace("[", "").replace("]", ""))
+        Ok(())
     }
 }
 
 impl From<Vec<Vec<char>>> for WaitingArea {
-    fn from(mut spots: Vec<Vec<char>>) -> Self {
-        WaitingArea::pad_with_empty(&mut spots);
+    fn from(spots: Vec<Vec<char>>) -> Self {
         Self {
-            spots: spots
-                .iter()
-                .map(|a| a.iter().map(|&c| Spot::from(c)).collect())
-                .collect(),
+            spots: Grid::new(
+                spots
+                    .iter()
+                    .map(|a| a.iter().map(|&c| Spot::from(c)).collect())
+                    .collect(),
+            ),
         }
     }
 }
 
 impl WaitingArea {
-    fn step(&mut self) -> WaitingArea {
-        let mut clone = self.clone();
-        for x in 1..self.spots.len() - 1 {
-            for y in 1..self.spots[0].len() - 1 {
-                let surroundings = self.create_3x3(x, y);
-                clone.spots[x][y] = Spot::outcome(surroundings);
-            }
+    fn pixels(&self, scale: usize) -> impl Iterator<Item = Spot> + '_ {
+        self.spots.rows().flat_map(move |row| {
+            (0..scale).flat_map(move |_| {
+                row.iter()
+                    .flat_map(move |&spot| (0..scale).map(move |_| spot))
+            })
+        })
+    }
+
+    fn step(&self, rules: SeatingRules) -> WaitingArea {
+        let mut automaton = rules.automaton(self.spots.clone());
+        automaton.step();
+        WaitingArea {
+            spots: automaton.grid().clone(),
         }
-        clone
     }
 
-    fn find_stable_state_p1(&self) -> usize {
-        let mut curr = self.clone();
-        let mut next = curr.step();
-        while curr != next {
-            next = next.step();
-            curr = curr.step();
+    pub fn find_stable_state(&self, rules: SeatingRules) -> Option<usize> {
+        match self.settle(rules, MAX_GENERATIONS) {
+            Outcome::Stable { occupied, .. } => Some(occupied),
+            _ => None,
         }
-        curr.spots
-            .iter()
-            .flatten()
-            .filter(|s| matches!(s, Spot::Occupied))
-            .count()
     }
 
-    fn find_stable_state_p2(&self) -> usize {
-        let mut curr = self.clone();
-        let mut next = curr.step_rtx();
-        while curr != next {
-            next = next.step_rtx();
-            curr = curr.step_rtx();
+    pub fn settle(&self, rules: SeatingRules, max_generations: usize) -> Outcome {
+        let mut automaton = rules.automaton(self.spots.clone());
+        let settled = automaton.settle(max_generations);
+        Outcome::new(settled, automaton.grid())
+    }
+
+    /// when the seating cycles, this stops at the first repeat
+    pub fn simulate(&self, rules: SeatingRules) -> History {
+        let mut automaton = rules.automaton(self.spots.clone());
+        let mut frames = vec![self.clone()];
+        let settled = automaton.settle_with(MAX_GENERATIONS, |spots| {
+            frames.push(WaitingArea {
+                spots: spots.clone(),
+            })
+        });
+        History {
+            outcome: Outcome::new(settled, automaton.grid()),
+            frames,
         }
-        curr.spots
-            .iter()
-            .flatten()
-            .filter(|s| matches!(s, Spot::Occupied))
-            .count()
-    }
-
-    fn pad_with_empty(spots: &mut Vec<Vec<char>>) {
-        spots.push(vec!['.'; spots[0].len()]);
-        spots.insert(0, vec!['.'; spots[0].len()]);
-        for row in spots {
-            row.insert(0, '.');
-            row.push('.')
+    }
+
+    pub fn occupied(&self) -> usize {
+        self.spots.alive_count()
+    }
+}
+
+pub struct History {
+    frames: Vec<WaitingArea>,
+    outcome: Outcome,
+}
+
+impl History {
+    pub fn frames(&self) -> &[WaitingArea] {
+        &self.frames
+    }
+
+    pub fn outcome(&self) -> Outcome {
+        self.outcome
+    }
+
+    pub fn occupied_counts(&self) -> Vec<usize> {
+        self.frames.iter().map(WaitingArea::occupied).collect()
+    }
+
+    pub fn write_text<W: Write>(&self, mut out: W) -> io::Result<()> {
+        for (generation, frame) in self.frames.iter().enumerate() {
+            if generation > 0 {
+                writeln!(out)?;
+            }
+            writeln!(
+                out,
+                "generation {}: {} occupied",
+                generation,
+                frame.occupied()
+            )?;
+            write!(out, "{}", frame)?;
         }
+        Ok(())
     }
 
-    fn create_3x3_rtx(&self, x: usize, y: usize) -> [[Spot; 3]; 3] {
-        let ix = x as i32;
-        let iy = y as i32;
-        let north = self.find_in_dir((ix, iy), (1, 0));
-        let south = self.find_in_dir((ix, iy), (-1, 0));
-        let east = self.find_in_dir((ix, iy), (0, -1));
-        let west = self.find_in_dir((ix, iy), (0, 1));
-        let nw = self.find_in_dir((ix, iy), (1, 1));
-        let ne = self.find_in_dir((ix, iy), (1, -1));
-        let sw = self.find_in_dir((ix, iy), (-1, 1));
-        let se = self.find_in_dir((ix, iy), (-1, -1));
-        let focus = self.spots[x][y];
-        [[nw, north, ne], [west, focus, east], [sw, south, se]]
-    }
-
-    fn create_3x3(&self, x: usize, y: usize) -> [[Spot; 3]; 3] {
-        [
-            [
-                self.spots[x - 1][y - 1],
-                self.spots[x - 1][y],
-                self.spots[x - 1][y + 1],
-            ],
-            [self.spots[x][y - 1], self.spots[x][y], self.spots[x][y + 1]],
-            [
-                self.spots[x + 1][y - 1],
-                self.spots[x + 1][y],
-                self.spots[x + 1][y + 1],
-            ],
-        ]
-    }
-
-    fn find_in_dir(&self, location: (i32, i32), direction: (i32, i32)) -> Spot {
-        let mut i = 1;
-        loop {
-            let ray: (usize, usize) = (
-                (location.0 + (direction.0 * i)) as usize,
-                (location.1 + (direction.1 * i)) as usize,
-            );
-            if let Some(spot) = self.spots.get(ray.0).unwrap_or(&Vec::new()).get(ray.1) {
-                if !(*spot == Spot::Floor) {
-                    return *spot;
-                } else {
-                    i += 1
+    pub fn write_ansi<W: Write>(&self, mut out: W, delay: Duration) -> io::Result<()> {
+        for (generation, frame) in self.frames.iter().enumerate() {
+            // move to the top left and clear the screen
+            write!(out, "\x1b[H\x1b[2J")?;
+            writeln!(
+                out,
+                "generation {}: {} occupied",
+                generation,
+                frame.occupied()
+            )?;
+            for row in frame.spots.rows() {
+                for spot in row {
+                    write!(out, "{}", spot.ansi())?;
                 }
-            } else {
-                return Spot::Empty;
+                writeln!(out)?;
+            }
+            out.flush()?;
+            thread::sleep(delay);
+        }
+        Ok(())
+    }
+
+    /// Tools like ffmpeg read the concatenated images as a video.
+    pub fn write_ppm<W: Write>(&self, mut out: W, scale: usize) -> io::Result<()> {
+        for frame in &self.frames {
+            let (width, height) = (frame.spots.width(), frame.spots.height());
+            write!(out, "P6\n{} {}\n255\n", width * scale, height * scale)?;
+            for spot in frame.pixels(scale) {
+                out.write_all(&spot.rgb())?;
             }
         }
+        Ok(())
     }
-    fn step_rtx(&self) -> WaitingArea {
-        let mut clone = self.clone();
-        for x in 1..self.spots.len() - 1 {
-            for y in 1..self.spots[0].len() - 1 {
-                let surroundings = self.create_3x3_rtx(x, y);
-                clone.spots[x][y] = Spot::outcome_v2(surroundings);
+
+    /// The image data isn't actually compressed, which keeps the encoder simple.
+    pub fn write_gif<W: Write>(&self, mut out: W, scale: usize, delay: Duration) -> io::Result<()> {
+        const PALETTE: [Spot; 3] = [Spot::Floor, Spot::Empty, Spot::Occupied];
+        let first = match self.frames.first() {
+            Some(first) => first,
+            None => return Ok(()),
+        };
+        let too_big = || io::Error::new(io::ErrorKind::InvalidInput, "too big for a GIF");
+        let width = u16::try_from(first.spots.width() * scale).map_err(|_| too_big())?;
+        let height = u16::try_from(first.spots.height() * scale).map_err(|_| too_big())?;
+        let centiseconds = u16::try_from(delay.as_millis() / 10).unwrap_or(u16::MAX);
+
+        out.write_all(b"GIF89a")?;
+        out.write_all(&width.to_le_bytes())?;
+        out.write_all(&height.to_le_bytes())?;
+        // a global colour table of four colours, the palette and one unused
+        out.write_all(&[0x81, 0, 0])?;
+        for spot in &PALETTE {
+            out.write_all(&spot.rgb())?;
+        }
+        out.write_all(&[0, 0, 0])?;
+        // loop forever
+        out.write_all(b"\x21\xff\x0bNETSCAPE2.0\x03\x01\x00\x00\x00")?;
+
+        for frame in &self.frames {
+            out.write_all(&[0x21, 0xf9, 4, 0])?;
+            out.write_all(&centiseconds.to_le_bytes())?;
+            out.write_all(&[0, 0, 0x2c, 0, 0, 0, 0])?;
+            out.write_all(&width.to_le_bytes())?;
+            out.write_all(&height.to_le_bytes())?;
+            out.write_all(&[0])?;
+            let indices = frame
+                .pixels(scale)
+                .map(|spot| PALETTE.iter().position(|&p| p == spot).unwrap() as u16);
+            let data = uncompressed_lzw(indices);
+            out.write_all(&[2])?;
+            for block in data.chunks(255) {
+                out.write_all(&[block.len() as u8])?;
+                out.write_all(block)?;
             }
+            out.write_all(&[0])?;
+        }
+        out.write_all(&[0x3b])
+    }
+}
+
+/// GIF image data for 2 bit colour indices, packed least significant bit first. Every code is a
+/// single pixel, with a clear code every two pixels so the decoder's table never grows enough
+/// to need codes wider than 3 bits.
+fn uncompressed_lzw<I: Iterator<Item = u16>>(indices: I) -> Vec<u8> {
+    const CLEAR: u16 = 4;
+    const END: u16 = 5;
+    let mut bytes = Vec::new();
+    let mut buffer = 0_u32;
+    let mut bits = 0;
+    let mut push = |code: u16| {
+        buffer |= u32::from(code) << bits;
+        bits += 3;
+        while bits >= 8 {
+            bytes.push(buffer as u8);
+            buffer >>= 8;
+            bits -= 8;
+        }
+    };
+    for (i, index) in indices.enumerate() {
+        if i % 2 == 0 {
+            push(CLEAR);
         }
-        clone
+        push(index);
     }
+    push(END);
+    if bits > 0 {
+        bytes.push(buffer as u8);
+    }
+    bytes
 }
 
 #[cfg(test)]
 mod tests {
-    use crate::day11::WaitingArea;
+    use crate::day11::{
+        uncompressed_lzw, Outcome, SeatingRules, Spot, WaitingArea, MAX_GENERATIONS,
+    };
     use crate::loader::file_to_vec;
+    use std::time::Duration;
 
     #[test]
     fn test_parse() {
@@ -234,31 +361,188 @@ mod tests {
 
     #[test]
     fn test_step() {
-        let mut a = fromfile("inputs/day11small.txt");
-        let mut next = a.step();
+        let a = fromfile("inputs/day11small.txt");
+        let mut next = a.step(SeatingRules::Adjacent);
         let mut curr = a.clone();
         while curr != next {
-            next = next.step();
-            curr = curr.step();
+            next = next.step(SeatingRules::Adjacent);
+            curr = curr.step(SeatingRules::Adjacent);
         }
     }
 
     #[test]
     fn test_count_steady_state() {
         let a = fromfile("inputs/day11small.txt");
-        println!("{}", a.find_stable_state_p1());
+        assert_eq!(a.find_stable_state(SeatingRules::Adjacent), Some(37));
+        assert_eq!(a.find_stable_state(SeatingRules::LineOfSight), Some(26));
     }
 
     #[test]
     fn test_p1() {
         let a = fromfile("inputs/day11.txt");
-        println!("{}", a.find_stable_state_p1());
+        assert_eq!(a.find_stable_state(SeatingRules::Adjacent), Some(2254));
     }
 
     #[test]
     fn test_p2() {
         let a = fromfile("inputs/day11.txt");
-        println!("{}", a.find_stable_state_p2());
+        assert_eq!(a.find_stable_state(SeatingRules::LineOfSight), Some(2004));
+    }
+
+    #[test]
+    fn test_line_of_sight() {
+        let sees = |rows: &[&str], row: usize, col: usize| {
+            let area = WaitingArea::from(
+                rows.iter()
+                    .map(|row| row.chars().collect())
+                    .collect::<Vec<Vec<char>>>(),
+            );
+            SeatingRules::LineOfSight
+                .automaton(area.spots)
+                .live_neighbors(row, col)
+        };
+        let crowded = [
+            ".......#.",
+            "...#.....",
+            ".#.......",
+            ".........",
+            "..#L....#",
+            "....#....",
+            ".........",
+            "#........",
+            "...#.....",
+        ];
+        assert_eq!(sees(&crowded, 4, 3), 8);
+        // the empty seat blocks the view of everything behind it
+        assert_eq!(
+            sees(&[".............", ".L.L.#.#.#.#.", "............."], 1, 1),
+            0
+        );
+        let surrounded = [
+            ".##.##.", "#.#.#.#", "##...##", "...L...", "##...##", "#.#.#.#", ".##.##.",
+        ];
+        assert_eq!(sees(&surrounded, 3, 3), 0);
+        // the corner seat sees across the grid, and nothing rather than wrapping around when it
+        // looks off an edge
+        assert_eq!(sees(&["L.#", "...", "#.#"], 0, 0), 3);
+        assert_eq!(sees(&["#.#", "...", "#.L"], 2, 2), 3);
+    }
+
+    #[test]
+    fn test_settle() {
+        let a = fromfile("inputs/day11small.txt");
+        assert_eq!(
+            a.settle(SeatingRules::LineOfSight, MAX_GENERATIONS),
+            Outcome::Stable {
+                generation: 6,
+                occupied: 26
+            }
+        );
+        assert_eq!(
+            a.settle(SeatingRules::LineOfSight, 3),
+            Outcome::GenerationLimit
+        );
+        // nobody tolerates a single neighbour, so the room fills and empties forever
+        let fussy = SeatingRules::Custom {
+            line_of_sight: false,
+            crowd: 1,
+        };
+        assert_eq!(
+            a.settle(fussy, MAX_GENERATIONS),
+            Outcome::Cycle {
+                start: 0,
+                period: 2
+            }
+        );
+        assert_eq!(a.find_stable_state(fussy), None);
+        assert_eq!(a.simulate(fussy).frames().len(), 2);
+        assert_eq!(
+            a.settle(
+                SeatingRules::Custom {
+                    line_of_sight: false,
+                    crowd: 4
+                },
+                MAX_GENERATIONS
+            ),
+            a.settle(SeatingRules::Adjacent, MAX_GENERATIONS)
+        );
+    }
+
+    #[test]
+    fn test_display() {
+        let a = fromfile("inputs/day11small.txt");
+        let expected = file_to_vec::<String>("inputs/day11small.txt").join("\n") + "\n";
+        assert_eq!(a.to_string(), expected);
+    }
+
+    #[test]
+    fn test_history() {
+        let history = fromfile("inputs/day11small.txt").simulate(SeatingRules::Adjacent);
+        assert_eq!(history.occupied_counts(), vec![0, 71, 20, 51, 30, 37]);
+        assert_eq!(
+            history.outcome(),
+            Outcome::Stable {
+                generation: 5,
+                occupied: 37
+            }
+        );
+
+        let mut text = Vec::new();
+        history.write_text(&mut text).unwrap();
+        let text = String::from_utf8(text).unwrap();
+        assert!(text.starts_with("generation 0: 0 occupied\nL.LL.LL.LL\n"));
+        assert!(text.contains("\n\ngeneration 5: 37 occupied\n#.#L.L#.##\n"));
+
+        let mut ansi = Vec::new();
+        history
+            .write_ansi(&mut ansi, Duration::from_millis(0))
+            .unwrap();
+        let ansi = String::from_utf8(ansi).unwrap();
+        assert_eq!(ansi.matches("\x1b[2J").count(), 6);
+    }
+
+    #[test]
+    fn test_image_output() {
+        let history = fromfile("inputs/day11small.txt").simulate(SeatingRules::Adjacent);
+        let mut ppm = Vec::new();
+        history.write_ppm(&mut ppm, 2).unwrap();
+        let frame_len = "P6\n20 20\n255\n".len() + 20 * 20 * 3;
+        assert_eq!(ppm.len(), 6 * frame_len);
+        assert!(ppm[frame_len..].starts_with(b"P6\n20 20\n255\n"));
+        // the top left seat is occupied after one generation, floor right of it
+        let pixel = |x: usize, y: usize| {
+            let start = frame_len + "P6\n20 20\n255\n".len() + (y * 20 + x) * 3;
+            [ppm[start], ppm[start + 1], ppm[start + 2]]
+        };
+        assert_eq!(pixel(1, 1), Spot::Occupied.rgb());
+        assert_eq!(pixel(2, 1), Spot::Floor.rgb());
+
+        let mut gif = Vec::new();
+        history
+            .write_gif(&mut gif, 1, Duration::from_millis(500))
+            .unwrap();
+        assert!(gif.starts_with(b"GIF89a\x0a\x00\x0a\x00"));
+        assert_eq!(gif.last(), Some(&0x3b));
+        assert_eq!(gif.windows(3).filter(|w| w == b"\x21\xf9\x04").count(), 6);
+    }
+
+    #[test]
+    fn test_uncompressed_lzw() {
+        // clear 1 2 clear 3 end, three bits each filling bytes from the low end
+        assert_eq!(
+            uncompressed_lzw(vec![1, 2, 3].into_iter()),
+            vec![0b1000_1100, 0b1011_1000, 0b10]
+        );
+    }
+
+    #[test]
+    fn test_bench_p1_p2() {
+        let a = fromfile("inputs/day11.txt");
+        timed!(a.find_stable_state(SeatingRules::Adjacent), "adjacent");
+        timed!(
+            a.find_stable_state(SeatingRules::LineOfSight),
+            "line of sight"
+        );
     }
 
     fn fromfile(path: &str) -> WaitingArea {
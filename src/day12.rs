@@ -3,7 +3,7 @@ use std::ops::Add;
 use std::str::FromStr;
 
 #[derive(Debug, Copy, Clone)]
-enum Action {
+pub enum Action {
     North(i32),
     South(i32),
     East(i32),
@@ -30,16 +30,41 @@ impl FromStr for Action {
     }
 }
 
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum NavigationMode {
+    /// part one, N/S/E/W move the ship, which starts facing east
+    Heading,
+    /// part two, N/S/E/W move the waypoint, which starts 10 east and 1 north
+    Waypoint,
+}
+
 #[derive(Debug)]
-struct Ship {
+pub struct Ship {
     position: (i32, i32),
-    waypoint: (i32, i32),
+    /// how far the ship goes for each unit forward, its heading or the waypoint
+    direction: (i32, i32),
+    mode: NavigationMode,
 }
 
 impl Ship {
-    fn manhattan_distance(&self) -> i32 {
+    pub fn new(mode: NavigationMode) -> Ship {
+        Ship {
+            position: (0, 0),
+            direction: match mode {
+                NavigationMode::Heading => (0, 1),
+                NavigationMode::Waypoint => (1, 10),
+            },
+            mode,
+        }
+    }
+
+    pub fn manhattan_distance(&self) -> i32 {
         self.position.0.abs() + self.position.1.abs()
     }
+
+    pub fn navigate<I: IntoIterator<Item = Action>>(self, actions: I) -> Ship {
+        actions.into_iter().fold(self, |ship, action| ship + action)
+    }
 }
 
 impl Add<Action> for Ship {
@@ -47,20 +72,27 @@ impl Add<Action> for Ship {
 
     fn add(self, rhs: Action) -> Self::Output {
         let mut position = self.position;
-        let mut waypoint = self.waypoint;
+        let mut direction = self.direction;
+        let moved = match self.mode {
+            NavigationMode::Heading => &mut position,
+            NavigationMode::Waypoint => &mut direction,
+        };
         match rhs {
-            Action::North(v) => waypoint.0 += v,
-            Action::South(v) => waypoint.0 -= v,
-            Action::East(v) => waypoint.1 += v,
-            Action::West(v) => waypoint.1 -= v,
-            Action::Right(v) => waypoint = rotate(waypoint, v),
-            Action::Left(v) => waypoint = rotate(waypoint, -v),
+            Action::North(v) => moved.0 += v,
+            Action::South(v) => moved.0 -= v,
+            Action::East(v) => moved.1 += v,
+            Action::West(v) => moved.1 -= v,
+            Action::Right(v) => direction = rotate(direction, v),
+            Action::Left(v) => direction = rotate(direction, -v),
             Action::Forward(v) => {
-                position = (position.0 + waypoint.0 * v, position.1 + waypoint.1 * v)
+                position = (position.0 + direction.0 * v, position.1 + direction.1 * v)
             }
         }
-        //println!("due to {:?}, the ship moved from {:?} with bearing {:?} to {:?} with bearing {:?} and moved the waypoint from {:?} to {:?}", rhs, self.position, self.direction, position, direction, self.waypoint, waypoint);
-        Ship { position, waypoint }
+        Ship {
+            position,
+            direction,
+            mode: self.mode,
+        }
     }
 }
 
@@ -76,7 +108,7 @@ fn rotate(waypoint: (i32, i32), degrees: i32) -> (i32, i32) {
 
 #[cfg(test)]
 mod tests {
-    use crate::day12::{Action, Ship};
+    use crate::day12::{Action, NavigationMode, Ship};
     use crate::loader::file_to_vec;
 
     #[test]
@@ -84,16 +116,30 @@ mod tests {
         file_to_vec::<Action>("inputs/day12small.txt");
     }
 
+    #[test]
+    fn test_small_heading() {
+        let ship = Ship::new(NavigationMode::Heading)
+            .navigate(file_to_vec::<Action>("inputs/day12small.txt"));
+        assert_eq!(ship.position, (-8, 17));
+        assert_eq!(ship.direction, (-1, 0));
+        assert_eq!(ship.manhattan_distance(), 25);
+    }
+
+    #[test]
+    fn test_heading() {
+        assert_eq!(
+            Ship::new(NavigationMode::Heading)
+                .navigate(file_to_vec::<Action>("inputs/day12.txt"))
+                .manhattan_distance(),
+            319
+        );
+    }
+
     #[test]
     fn test_small_instr() {
-        let ship = Ship {
-            position: (0, 0),
-            waypoint: (1, 10),
-        };
         assert_eq!(
-            file_to_vec::<Action>("inputs/day12small.txt")
-                .iter()
-                .fold(ship, |acc, x| { acc + *x })
+            Ship::new(NavigationMode::Waypoint)
+                .navigate(file_to_vec::<Action>("inputs/day12small.txt"))
                 .manhattan_distance(),
             286
         );
@@ -101,14 +147,9 @@ mod tests {
 
     #[test]
     fn test_instr() {
-        let ship = Ship {
-            position: (0, 0),
-            waypoint: (1, 10),
-        };
         assert_eq!(
-            file_to_vec::<Action>("inputs/day12.txt")
-                .iter()
-                .fold(ship, |acc, x| { acc + *x })
+            Ship::new(NavigationMode::Waypoint)
+                .navigate(file_to_vec::<Action>("inputs/day12.txt"))
                 .manhattan_distance(),
             50157
         );
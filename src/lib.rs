@@ -10,6 +10,7 @@
 #[macro_use]
 mod timer;
 pub mod loader;
+pub mod automaton;
 
 pub mod day01;
 pub mod day02;
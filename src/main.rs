@@ -0,0 +1,75 @@
+use advent::day08::{Debugger, Instruction, Program};
+use advent::day09::XMASValidator;
+use advent::day11::{SeatingRules, WaitingArea};
+use advent::day12::{Action, NavigationMode, Ship};
+use advent::loader::file_to_vec;
+use std::env;
+use std::io;
+use std::time::Duration;
+
+fn main() {
+    let args = env::args().skip(1).collect::<Vec<String>>();
+    let args = args.iter().map(String::as_str).collect::<Vec<&str>>();
+    match args.as_slice() {
+        ["day08", "debug", path] => {
+            let program = Program::new(file_to_vec::<Instruction>(path));
+            Debugger::new(program)
+                .repl(io::stdin().lock(), io::stdout())
+                .unwrap()
+        }
+        ["day09", "validate", preamble_len] => match preamble_len.parse() {
+            Ok(preamble_len) => {
+                let stdin = io::stdin();
+                for rule_breaker in
+                    XMASValidator::<i64>::new(preamble_len).rule_breakers_in(stdin.lock())
+                {
+                    match rule_breaker {
+                        Ok((position, value)) => println!("line {}: {}", position + 1, value),
+                        Err(error) => eprintln!("{}", error),
+                    }
+                }
+            }
+            Err(_) => eprintln!("expected a preamble length, instead got {}", preamble_len),
+        },
+        ["day11", format, rules, path] => {
+            let rules = match *rules {
+                "adjacent" => SeatingRules::Adjacent,
+                "sight" => SeatingRules::LineOfSight,
+                _ => return eprintln!("expected adjacent or sight, instead got {}", rules),
+            };
+            let area = WaitingArea::from(
+                file_to_vec::<String>(path)
+                    .iter()
+                    .map(|s| s.chars().collect())
+                    .collect::<Vec<Vec<char>>>(),
+            );
+            let history = area.simulate(rules);
+            let delay = Duration::from_millis(200);
+            let stdout = io::stdout();
+            let out = stdout.lock();
+            match *format {
+                "text" => history.write_text(out),
+                "ansi" => history.write_ansi(out, delay),
+                "ppm" => history.write_ppm(out, 4),
+                "gif" => history.write_gif(out, 4, delay),
+                _ => return eprintln!("expected text, ansi, ppm or gif, instead got {}", format),
+            }
+            .unwrap()
+        }
+        ["day12", mode, path] => {
+            let mode = match *mode {
+                "heading" => NavigationMode::Heading,
+                "waypoint" => NavigationMode::Waypoint,
+                _ => return eprintln!("expected heading or waypoint, instead got {}", mode),
+            };
+            let ship = Ship::new(mode).navigate(file_to_vec::<Action>(path));
+            println!("{}", ship.manhattan_distance())
+        }
+        _ => eprintln!(
+            "usage: advent day08 debug <program>\n       \
+             advent day09 validate <preamble length>\n       \
+             advent day11 <text|ansi|ppm|gif> <adjacent|sight> <seating chart>\n       \
+             advent day12 <heading|waypoint> <navigation instructions>"
+        ),
+    }
+}